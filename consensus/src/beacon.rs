@@ -0,0 +1,76 @@
+use crate::config::{EpochNumber, ViewNumber};
+use crate::error::{ConsensusError, ConsensusResult};
+use crypto::Digest;
+use ed25519_dalek::Digest as _;
+use ed25519_dalek::Sha512;
+use serde::{Deserialize, Serialize};
+use std::convert::TryInto;
+use std::fmt;
+use threshold_crypto::{PublicKeySet, SIG_SIZE};
+
+#[cfg(test)]
+#[path = "tests/beacon_tests.rs"]
+pub mod beacon_tests;
+
+// Output of the randomness beacon for one epoch.
+// The signature is the threshold signature combined out of f+1 `RandomnessShare` of the
+// view that elected the committed leader. No coalition of at most f nodes can predict or
+// bias it, so applications may use `seed` as shared unbiasable randomness.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Beacon {
+    pub epoch: EpochNumber,
+    pub view: ViewNumber,
+    pub signature: Vec<u8>,
+    pub seed: Digest,
+}
+
+impl Beacon {
+    pub fn new(epoch: EpochNumber, view: ViewNumber, signature: &threshold_crypto::Signature) -> Self {
+        let signature = signature.to_bytes().to_vec();
+        let seed = Self::derive_seed(&signature);
+        Self {
+            epoch,
+            view,
+            signature,
+            seed,
+        }
+    }
+
+    // Key under which the beacon of a committed epoch is persisted.
+    pub fn key(epoch: EpochNumber) -> Vec<u8> {
+        digest!(epoch.to_le_bytes(), "BEACON").to_vec()
+    }
+
+    fn derive_seed(signature: &[u8]) -> Digest {
+        digest!(signature, "BEACON_SEED")
+    }
+
+    // Check the beacon against the tss public key set, without any other consensus state.
+    pub fn verify(&self, pk_set: &PublicKeySet) -> ConsensusResult<()> {
+        let bytes: [u8; SIG_SIZE] = self.signature
+            .as_slice()
+            .try_into()
+            .map_err(|_| ConsensusError::InvalidBeacon(self.epoch))?;
+        let signature = threshold_crypto::Signature::from_bytes(bytes)
+            .map_err(|_| ConsensusError::InvalidBeacon(self.epoch))?;
+
+        // The beacon is the random coin, i.e. a signature against <epoch, view, RANDOMNESS_SHARE>.
+        let digest = digest!(self.epoch.to_le_bytes(), self.view.to_le_bytes(), "RANDOMNESS_SHARE");
+        ensure!(
+            pk_set.public_key().verify(&signature, digest),
+            ConsensusError::InvalidBeacon(self.epoch)
+        );
+
+        ensure!(
+            self.seed == Self::derive_seed(&self.signature),
+            ConsensusError::InvalidBeacon(self.epoch)
+        );
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Beacon {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "Beacon(epoch {}, view {}, seed {})", self.epoch, self.view, self.seed)
+    }
+}
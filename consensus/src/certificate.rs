@@ -0,0 +1,59 @@
+use crate::config::{Committee, Stake};
+use crate::error::{ConsensusError, ConsensusResult};
+use crypto::{Digest, PublicKey, Signature};
+use ed25519_dalek::Digest as _;
+use ed25519_dalek::Sha512;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::convert::TryInto;
+use std::fmt;
+
+// Proof that at least f+1 nodes (hence at least one honest node) stored a payload, so that
+// anyone can retrieve it later. The mempool of the payload creator gathers the votes.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Certificate {
+    pub digest: Digest,
+    pub votes: Vec<(PublicKey, Signature)>,
+}
+
+impl Certificate {
+    // What a node signs to vote for the availability of a payload.
+    pub fn vote_digest(digest: &Digest) -> Digest {
+        digest!(digest, "AVAILABLE")
+    }
+
+    // Key under which the certificate of a payload is persisted.
+    pub fn key(digest: &Digest) -> Vec<u8> {
+        digest!(digest, "CERTIFICATE").to_vec()
+    }
+
+    pub fn verify(&self, committee: &Committee) -> ConsensusResult<()> {
+        // Ensure the certificate has a quorum.
+        let mut weight: Stake = 0;
+        let mut used = HashSet::new();
+        for (name, _) in &self.votes {
+            ensure!(
+                !used.contains(name),
+                ConsensusError::AuthorityReuseinCertificate(*name)
+            );
+            let voting_rights = committee.stake(name);
+            ensure!(voting_rights > 0, ConsensusError::UnknownAuthority(*name));
+            used.insert(*name);
+            weight += voting_rights;
+        }
+        ensure!(
+            weight >= committee.validity_threshold(),
+            ConsensusError::CertificateRequiresQuorum(self.digest.clone())
+        );
+
+        // Check the signatures.
+        Signature::verify_batch(&Self::vote_digest(&self.digest), &self.votes)
+            .map_err(ConsensusError::from)
+    }
+}
+
+impl fmt::Debug for Certificate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "C({}, {} votes)", self.digest, self.votes.len())
+    }
+}
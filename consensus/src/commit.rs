@@ -0,0 +1,74 @@
+use crate::beacon::Beacon;
+use crate::config::{Committee, EpochNumber};
+use crate::error::{ConsensusError, ConsensusResult};
+use crate::messages::Block;
+use crypto::{Digest, Hash as _};
+use ed25519_dalek::Digest as _;
+use ed25519_dalek::Sha512;
+use std::convert::TryInto;
+use store::Store;
+use threshold_crypto::PublicKeySet;
+
+#[cfg(test)]
+#[path = "tests/commit_tests.rs"]
+pub mod commit_tests;
+
+// What the consensus outputs to the application for every committed epoch.
+#[derive(Clone, Debug)]
+pub struct CommittedBlock {
+    pub block: Block,
+    // None if the coin of the epoch could not be turned into a beacon.
+    pub beacon: Option<Beacon>,
+    // Commit time in milliseconds since the UNIX epoch, monotonic along the committed chain.
+    pub timestamp: u64,
+}
+
+impl CommittedBlock {
+    // Key under which the committed block of an epoch is persisted.
+    pub fn key(epoch: EpochNumber) -> Vec<u8> {
+        digest!(epoch.to_le_bytes(), "COMMITTED").to_vec()
+    }
+
+    // Key under which the last committed epoch is persisted.
+    pub fn last_key() -> Vec<u8> {
+        digest!("LAST_COMMITTED").to_vec()
+    }
+}
+
+// Walk the committed blocks persisted in the store, from the first epoch to the last committed
+// one, and check that none is missing, each of them extends its predecessor and carries valid
+// sigma1 and sigma2. Returns the last epoch of the audited chain.
+pub async fn audit_chain(
+    store: &mut Store,
+    committee: &Committee,
+    pk_set: &PublicKeySet,
+) -> ConsensusResult<EpochNumber> {
+    let last: EpochNumber = match store.read(CommittedBlock::last_key()).await? {
+        Some(bytes) => bincode::deserialize(&bytes)?,
+        None => 0,
+    };
+    let mut parent = Digest::default();
+    for epoch in 1..=last {
+        let bytes = store
+            .read(CommittedBlock::key(epoch))
+            .await?
+            .ok_or(ConsensusError::MissingCommittedBlock(epoch))?;
+        let block: Block = bincode::deserialize(&bytes)?;
+        ensure!(
+            block.epoch == epoch && block.parent == parent,
+            ConsensusError::BrokenChain(block.digest(), epoch)
+        );
+        ensure!(
+            committee.stake(&block.author) > 0,
+            ConsensusError::UnknownAuthority(block.author)
+        );
+        block.verify_signature()?;
+        ensure!(
+            block.check_sigma1(&pk_set.public_key()) && block.check_sigma2(&pk_set.public_key()),
+            ConsensusError::InvalidVoteProof(block.proof.clone())
+        );
+
+        parent = block.digest();
+    }
+    Ok(last)
+}
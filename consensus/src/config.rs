@@ -9,6 +9,7 @@ pub type EpochNumber = u128;
 pub type ViewNumber = u8;
 
 #[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct Parameters {
     pub timeout_delay: u64,
     pub sync_retry_delay: u64,
@@ -17,6 +18,10 @@ pub struct Parameters {
     pub min_block_delay: u64,
     pub ddos: bool,
     pub exp: u64,
+    pub max_clock_skew: u64,
+    pub max_idle_delay: u64,
+    pub availability_certificates: bool,
+    pub retention_depth: u64,
 }
 
 impl Default for Parameters {
@@ -29,6 +34,10 @@ impl Default for Parameters {
             max_payload_size: 500,
             ddos: false,
             exp: 1,
+            max_clock_skew: 5_000,
+            max_idle_delay: 1_000,
+            availability_certificates: false,
+            retention_depth: 0,
         }
     }
 }
@@ -94,6 +103,12 @@ impl Committee {
         total_votes - 1
     }
 
+    // Stake of f+1 nodes, ensuring at least one of them is honest.
+    pub fn validity_threshold(&self) -> Stake {
+        let total_votes: Stake = self.authorities.values().map(|x| x.stake).sum();
+        (total_votes - 1) / 3 + 1
+    }
+
     pub fn random_coin_threshold(&self) -> Stake {
         let total_votes: Stake = self.authorities.values().map(|x| x.stake).sum();
         (total_votes - 1) / 3 + 1
@@ -1,9 +1,11 @@
+use crate::commit::CommittedBlock;
 use crate::config::{Committee, Parameters};
 use crate::core::Core;
 use crate::error::ConsensusResult;
 use crate::filter::Filter;
 use crate::mempool::{ConsensusMempoolMessage, MempoolDriver};
-use crate::messages::{Block, ConsensusMessage};
+use crate::metrics::PacingMetrics;
+use crate::messages::ConsensusMessage;
 use crypto::{PublicKey, SignatureService};
 use log::info;
 use network::{NetReceiver, NetSender};
@@ -29,7 +31,8 @@ impl Consensus {
         tx_core: Sender<ConsensusMessage>,
         rx_core: Receiver<ConsensusMessage>,
         tx_consensus_mempool: Sender<ConsensusMempoolMessage>,
-        tx_commit: Sender<Block>,
+        tx_commit: Sender<CommittedBlock>,
+        metrics: PacingMetrics,
     ) -> ConsensusResult<()> {
         info!(
             "Consensus timeout delay set to {} ms",
@@ -47,6 +50,22 @@ impl Consensus {
             "Consensus min block delay set to {} ms",
             parameters.min_block_delay
         );
+        info!(
+            "Consensus max clock skew set to {} ms",
+            parameters.max_clock_skew
+        );
+        info!(
+            "Consensus max idle delay set to {} ms",
+            parameters.max_idle_delay
+        );
+        info!(
+            "Consensus availability certificates {}",
+            if parameters.availability_certificates { "enabled" } else { "disabled" }
+        );
+        info!(
+            "Consensus retention depth set to {} epochs",
+            parameters.retention_depth
+        );
 
         let (tx_network, rx_network) = channel(10000);
         let (tx_filter, rx_filter) = channel(10000);
@@ -83,6 +102,7 @@ impl Consensus {
             /* core_channel */ rx_core,
             /* network_filter */ tx_filter,
             /* commit_channel */ tx_commit,
+            metrics,
         ).await;
 
         tokio::spawn(async move {
@@ -2,9 +2,13 @@ use std::collections::{HashMap, BTreeMap, HashSet};
 use std::pin::Pin;
 use std::sync::{Arc, Mutex};
 use crate::aggregator::Aggregator;
+use crate::beacon::Beacon;
+use crate::certificate::Certificate;
+use crate::commit::CommittedBlock;
 use crate::config::{Committee, Parameters, EpochNumber, ViewNumber};
 use crate::filter::FilterInput;
 use crate::mempool::MempoolDriver;
+use crate::metrics::PacingMetrics;
 use crate::synchronizer::{ElectionState, ElectionFuture, transmit};
 use crate::error::{ConsensusError, ConsensusResult};
 use crate::messages::*;
@@ -18,6 +22,19 @@ use log::{debug, warn, error, info};
 use threshold_crypto::PublicKeySet;
 use tokio::sync::mpsc::{Receiver, Sender, channel};
 use store::Store;
+use tokio::time::{sleep, Duration, Instant};
+
+#[cfg(test)]
+#[path = "tests/core_tests.rs"]
+pub mod core_tests;
+
+// Interval at which an idle node polls the mempool for new payloads.
+const IDLE_POLL_DELAY: u64 = 10;
+
+// How many epochs ahead of the halt mark we keep the blocks whose parent we did not commit yet,
+// and how many messages per epoch.
+const MAX_DEFERRED_EPOCHS: EpochNumber = 10;
+const MAX_DEFERRED_MESSAGES: usize = 1_000;
 
 pub struct Core {
     name: PublicKey,
@@ -27,13 +44,15 @@ pub struct Core {
     pk_set: PublicKeySet,
 
     store: Store,
+    stored: BTreeMap<EpochNumber, Vec<Vec<u8>>>, // keys of the blocks we stored, by epoch
     mempool_driver: MempoolDriver,
     network_filter: Sender<FilterInput>,
 
     core_channel: Receiver<ConsensusMessage>,
     halt_channel: Sender<(Arc<Mutex<ElectionState>>, Block)>, // handle halts
+    synced_channel: Sender<EpochNumber>, // epochs committed from the blocks of peers
     advance_channel: Receiver<Block>, // propose block for next epoch
-    commit_channel: Sender<Block>,
+    commit_channel: Sender<CommittedBlock>,
 
     votes_aggregators: HashMap<(EpochNumber, Digest), Aggregator>, // n-f votes collector
     election_states: HashMap<(EpochNumber, ViewNumber), Arc<Mutex<ElectionState>>>, // stores states of leader election and block delivery
@@ -41,6 +60,19 @@ pub struct Core {
 
     halt_mark: EpochNumber,
     epochs_halted: HashSet<EpochNumber>,
+
+    committed: HashMap<EpochNumber, (Digest, u64)>, // digests and commit timestamps of recently committed blocks, to check new blocks
+    last_committed: Digest, // parent of the next block we propose
+    last_commit_timestamp: u64, // commit timestamps never go backwards
+    deferred: BTreeMap<EpochNumber, Vec<ConsensusMessage>>, // messages whose block extends a block we did not commit yet
+    commit_request: Option<(EpochNumber, Instant)>, // last epoch whose committed block we asked to peers
+    commit_replies: HashMap<(EpochNumber, Digest), Aggregator>, // f+1 replies collector
+
+    next_epoch: Option<EpochNumber>, // epoch waiting for us to propose
+    last_proposal: Instant,
+    idle_since: Instant,
+    idle: bool, // whether the mempool had nothing for the pending epoch
+    metrics: PacingMetrics,
 }
 
 impl Core {
@@ -51,18 +83,32 @@ impl Core {
         parameters: Parameters,
         signature_service: SignatureService,
         pk_set: PublicKeySet,
-        store: Store,
+        mut store: Store,
         mempool_driver: MempoolDriver,
         core_channel: Receiver<ConsensusMessage>,
         network_filter: Sender<FilterInput>,
-        commit_channel: Sender<Block>,
+        commit_channel: Sender<CommittedBlock>,
+        metrics: PacingMetrics,
     ) -> Self {
         let (tx_halt, mut rx_halt): (_, Receiver<(Arc<Mutex<ElectionState>>, Block)>) = channel(10000);
         let (tx_advance, rx_advance): (Sender<Block>, _) = channel(10000);
+        let (tx_synced, mut rx_synced): (_, Receiver<EpochNumber>) = channel(10000);
+
+        // Resume the committed chain where we left it.
+        let last = Self::load_committed(&mut store)
+            .await
+            .expect("Failed to load the last committed block");
+        let halt_mark = last.as_ref().map_or(0, |block| block.epoch);
+        let last_committed = last.as_ref().map_or_else(Digest::default, |block| block.digest());
+        let last_commit_timestamp = last.as_ref().map_or(0, |block| block.timestamp);
+        let mut committed = HashMap::new();
+        if halt_mark > 0 {
+            committed.insert(halt_mark, (last_committed.clone(), last_commit_timestamp));
+        }
 
         // Handle Halt till receives the leader.
         tokio::spawn(async move {
-            let mut halt_mark = 0;
+            let mut halt_mark = halt_mark;
             let mut epochs_halted = HashSet::new();
             let mut halts_unhandled = HashMap::<EpochNumber, Vec<Block>>::new();
             let mut waiting = FuturesUnordered::<Pin<Box<dyn Future<Output=RandomCoin> + Send>>>::new();
@@ -87,12 +133,18 @@ impl Core {
                             }
                             // Clean up halted.
                             epochs_halted.insert(coin.epoch);
-                            if epochs_halted.remove(&(halt_mark + 1)) {
+                            while epochs_halted.remove(&(halt_mark + 1)) {
                                 halt_mark += 1;
                             }
                             
                         }
                     },
+                    Some(epoch) = rx_synced.recv() => {
+                        epochs_halted.insert(epoch);
+                        while epochs_halted.remove(&(halt_mark + 1)) {
+                            halt_mark += 1;
+                        }
+                    },
                     else => break,
                 }
             }
@@ -105,20 +157,46 @@ impl Core {
             signature_service,
             pk_set,
             store,
+            stored: BTreeMap::new(),
             mempool_driver,
             network_filter,
             core_channel,
             commit_channel,
             halt_channel: tx_halt,
+            synced_channel: tx_synced,
             advance_channel: rx_advance,
             votes_aggregators: HashMap::new(),
             election_states: HashMap::new(),
             blocks_received: HashMap::new(),
-            halt_mark: 0,
+            halt_mark,
             epochs_halted: HashSet::new(),
+            committed,
+            last_committed,
+            last_commit_timestamp,
+            deferred: BTreeMap::new(),
+            commit_request: None,
+            commit_replies: HashMap::new(),
+            next_epoch: None,
+            last_proposal: Instant::now(),
+            idle_since: Instant::now(),
+            idle: false,
+            metrics,
         }
     }
 
+    // Load the last committed block persisted in the store, if any.
+    async fn load_committed(store: &mut Store) -> ConsensusResult<Option<Block>> {
+        let last: EpochNumber = match store.read(CommittedBlock::last_key()).await? {
+            Some(bytes) => bincode::deserialize(&bytes)?,
+            None => return Ok(None),
+        };
+        let bytes = store
+            .read(CommittedBlock::key(last))
+            .await?
+            .ok_or(ConsensusError::MissingCommittedBlock(last))?;
+        Ok(Some(bincode::deserialize(&bytes)?))
+    }
+
     // Get block by digest <epoch, view, author>.
     async fn read(&mut self, digest: &Digest) -> ConsensusResult<Block> {
         match self.store.read(digest.to_vec()).await? {
@@ -135,6 +213,9 @@ impl Core {
         let digest = digest!(block.epoch.to_le_bytes(), block.view.to_le_bytes(), block.author.0);
         let key = digest.to_vec();
         let value = bincode::serialize(block).expect("Failed to serialize block");
+        if self.parameters.retention_depth > 0 {
+            self.stored.entry(block.epoch).or_insert_with(Vec::new).push(key.clone());
+        }
         self.store.write(key, value).await;
     }
 
@@ -147,19 +228,138 @@ impl Core {
         self.blocks_received.get(&(author, epoch, view))
     }
 
+    // Check a block we received. The messages carrying a block whose parent we did not commit
+    // yet are processed again once we do; returns false for them.
+    async fn verify_block(&mut self, block: &Block, message: fn(Block) -> ConsensusMessage) -> ConsensusResult<bool> {
+        let result = block.verify(&self.committee, self.halt_mark, &self.epochs_halted, &self.committed, self.parameters.availability_certificates);
+        match result {
+            Ok(()) => Ok(true),
+            Err(ConsensusError::UnknownParent(digest, epoch)) => {
+                self.request_commit(epoch).await?;
+                if epoch > self.halt_mark + MAX_DEFERRED_EPOCHS {
+                    debug!("Dropping block {} of epoch {}: too far ahead", digest, epoch);
+                    return Ok(false);
+                }
+                let deferred = self.deferred.entry(epoch).or_insert_with(Vec::new);
+                if deferred.len() < MAX_DEFERRED_MESSAGES {
+                    debug!("Processing of {} deferred: missing parent", digest);
+                    deferred.push(message(block.clone()));
+                }
+                Ok(false)
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    // Ask the peers for the block committed in the epoch following our halt mark, once we see
+    // blocks of `epoch`: the parent of the next block may still be being agreed on, but not
+    // the grandparent. We ask again after `sync_retry_delay`.
+    async fn request_commit(&mut self, epoch: EpochNumber) -> ConsensusResult<()> {
+        if epoch <= self.halt_mark + 2 {
+            return Ok(());
+        }
+        let missing = self.halt_mark + 1;
+        if let Some((requested, time)) = &self.commit_request {
+            let delay = Duration::from_millis(self.parameters.sync_retry_delay);
+            if *requested == missing && time.elapsed() < delay {
+                return Ok(());
+            }
+        }
+        self.commit_request = Some((missing, Instant::now()));
+        debug!("Requesting the block committed in epoch {}", missing);
+        self.transmit(ConsensusMessage::CommitRequest(missing, self.name), None).await
+    }
+
+    async fn handle_commit_request(&mut self, epoch: EpochNumber, origin: PublicKey) -> ConsensusResult<()> {
+        ensure!(
+            self.committee.stake(&origin) > 0,
+            ConsensusError::UnknownAuthority(origin)
+        );
+        if let Some(bytes) = self.store.read(CommittedBlock::key(epoch)).await? {
+            let block = bincode::deserialize(&bytes)?;
+            self.transmit(ConsensusMessage::CommitReply(block, self.name), Some(&origin)).await?;
+        }
+        Ok(())
+    }
+
+    // Commit the block of the epoch following our halt mark once f+1 peers sent it to us as
+    // committed: at least one of them is honest.
+    async fn handle_commit_reply(&mut self, block: Block, author: PublicKey) -> ConsensusResult<()> {
+        ensure!(
+            self.committee.stake(&author) > 0,
+            ConsensusError::UnknownAuthority(author)
+        );
+        if block.epoch != self.halt_mark + 1 || self.epochs_halted.contains(&block.epoch) {
+            return Ok(());
+        }
+        block.verify(&self.committee, self.halt_mark, &self.epochs_halted, &self.committed, self.parameters.availability_certificates)?;
+        ensure!(
+            block.check_sigma1(&self.pk_set.public_key()) && block.check_sigma2(&self.pk_set.public_key()),
+            ConsensusError::InvalidVoteProof(block.proof.clone())
+        );
+
+        let threshold = self.committee.validity_threshold();
+        let stake = self.committee.stake(&author);
+        let replies = self.commit_replies
+            .entry((block.epoch, block.digest()))
+            .or_insert_with(|| Aggregator::new());
+        replies.append(author, ConsensusMessage::CommitReply(block.clone(), author), stake)?;
+        if replies.take(threshold).is_none() {
+            return Ok(());
+        }
+
+        debug!("Committing block {} of epoch {} from peers", block.digest(), block.epoch);
+        self.mempool_driver.prefetch(block.clone()).await;
+        self.output(&block).await?;
+        if let Err(e) = self.synced_channel.send(block.epoch).await {
+            panic!("Failed to send message through synced channel: {}", e);
+        }
+        self.enter_next_epoch(&block).await;
+
+        // Keep going if we are still behind.
+        match self.deferred.keys().next_back() {
+            Some(&epoch) => self.request_commit(epoch).await,
+            None => Ok(()),
+        }
+    }
+
+    // Process the messages whose block extends the block we just committed.
+    async fn resume(&mut self, epoch: EpochNumber) {
+        for message in self.deferred.remove(&epoch).unwrap_or_default() {
+            let result = match message {
+                ConsensusMessage::Val(block) => self.handle_val(block).await,
+                ConsensusMessage::Finish(finish) => self.handle_finish(&finish).await,
+                ConsensusMessage::Halt(block) => self.handle_halt(block).await,
+                _ => Ok(()),
+            };
+            if let Err(e) = result {
+                warn!("{}", e);
+            }
+        }
+    }
+
     // Generate a new block.
-    async fn generate_block(&mut self, epoch: EpochNumber, view: ViewNumber, proof: Proof) -> ConsensusResult<Block> {
-        // Get payloads.
-        let payload = self
-            .mempool_driver
-            .get(self.parameters.max_payload_size)
-            .await;
+    async fn generate_block(&mut self, epoch: EpochNumber, view: ViewNumber, proof: Proof, payload: Vec<Digest>) -> ConsensusResult<Block> {
+        // The mempool persists the certificate of every payload it hands out.
+        let mut certificates = Vec::new();
+        if self.parameters.availability_certificates {
+            for digest in &payload {
+                match self.store.read(Certificate::key(digest)).await? {
+                    Some(bytes) => certificates.push(bincode::deserialize(&bytes)?),
+                    None => bail!(ConsensusError::MissingCertificate(digest.clone())),
+                }
+            }
+        }
 
         let block = Block::new(
             payload,
+            certificates,
             self.name,
+            self.last_committed.clone(),
             epoch,
             view,
+            // Never propose a timestamp older than the one of the parent.
+            now().max(self.last_commit_timestamp),
             proof,
             self.signature_service.clone(),
         ).await;
@@ -256,7 +456,10 @@ impl Core {
 
     async fn handle_val(&mut self, block: Block) -> ConsensusResult<()> {
         // Check the block is correctly formed.
-        block.verify(&self.committee, self.halt_mark, &self.epochs_halted)?;
+        if !self.verify_block(&block, ConsensusMessage::Val).await? {
+            return Ok(());
+        }
+        block.verify_timestamp(self.parameters.max_clock_skew)?;
 
         // Validate block.
         ensure!(
@@ -265,8 +468,11 @@ impl Core {
         );
 
         // Let's see if we have the block's data. If we don't, the mempool
-        // will get it and then make us resume processing this block.
-        if !self.mempool_driver.verify(block.clone()).await? {
+        // will get it and then make us resume processing this block. Certified payloads
+        // are available anyway: the mempool fetches them in the background.
+        if self.parameters.availability_certificates {
+            self.mempool_driver.prefetch(block.clone()).await;
+        } else if !self.mempool_driver.verify(block.clone()).await? {
             debug!("Processing of {} suspended: missing payload", block.digest());
             return Ok(())
         }
@@ -320,6 +526,11 @@ impl Core {
     async fn handle_echo(&mut self, echo: &Echo) -> ConsensusResult<()> {
         echo.verify(&self.committee, &self.pk_set, self.name, self.halt_mark, &self.epochs_halted)?;
 
+        // The peers echoing a block hold its payload.
+        if let Some(block) = self.get_block(echo.block_author, echo.epoch, echo.view).cloned() {
+            self.mempool_driver.holders(&block, echo.author).await;
+        }
+
         self.votes_aggregators
             .entry((echo.epoch, echo.digest()))
             .or_insert_with(|| Aggregator::new())
@@ -387,7 +598,9 @@ impl Core {
     }
 
     async fn handle_finish(&mut self, finish: &Finish) -> ConsensusResult<()> {
-        finish.0.verify(&self.committee, self.halt_mark, &self.epochs_halted)?;
+        if !self.verify_block(&finish.0, |block| ConsensusMessage::Finish(Finish(block))).await? {
+            return Ok(());
+        }
 
         // Verify threshold signature.
         ensure!(
@@ -520,11 +733,8 @@ impl Core {
                 let threshold_signature = self.pk_set.combine_signatures(share_map).expect("Unqualified shares!");
 
                 // Use coin to elect leader. 
-                let id = usize::from_be_bytes((&threshold_signature.to_bytes()[0..8]).try_into().unwrap()) % self.committee.size();
-                let mut keys: Vec<_> = self.committee.authorities.keys().cloned().collect();
-                keys.sort();
-                let leader = keys[id];
-                debug!("Random coin of epoch {} view {} elects leader id {}", randomness_share.epoch, randomness_share.view, id);
+                let leader = RandomCoin::elect(&self.committee, &threshold_signature);
+                debug!("Random coin of epoch {} view {} elects leader id {}", randomness_share.epoch, randomness_share.view, self.committee.id(leader));
 
                 let random_coin = RandomCoin {
                     epoch: randomness_share.epoch,
@@ -641,6 +851,10 @@ impl Core {
 
     async fn handle_prevote(&mut self, prevote: &PreVote) -> ConsensusResult<()> {
         prevote.verify(&self.committee, &self.pk_set, self.halt_mark, &self.epochs_halted)?;
+        // The peers voting for a block hold its payload.
+        if let PreVoteEnum::Yes(block) = &prevote.body {
+            self.mempool_driver.holders(block, prevote.author).await;
+        }
 
         self.votes_aggregators
             .entry((prevote.epoch, prevote.digest()))
@@ -730,6 +944,9 @@ impl Core {
 
     async fn handle_vote(&mut self, vote: Vote) -> ConsensusResult<()> {
         vote.verify(&self.committee, &self.pk_set, self.halt_mark, &self.epochs_halted)?;
+        if let VoteEnum::Yes(block, _) = &vote.body {
+            self.mempool_driver.holders(block, vote.author).await;
+        }
 
         self.votes_aggregators
             .entry((vote.epoch, vote.digest()))
@@ -822,7 +1039,9 @@ impl Core {
     }
 
     async fn handle_halt(&mut self, block: Block) -> ConsensusResult<()> {
-        block.verify(&self.committee, self.halt_mark, &self.epochs_halted)?;
+        if !self.verify_block(&block, ConsensusMessage::Halt).await? {
+            return Ok(());
+        }
 
         ensure!(
             block.check_sigma1(&self.pk_set.public_key()) && block.check_sigma2(&self.pk_set.public_key()),
@@ -839,16 +1058,53 @@ impl Core {
         Ok(())
     }
 
+    // The beacon of an epoch is the coin that elected its committed leader.
+    fn beacon(&self, block: &Block) -> ConsensusResult<Beacon> {
+        let coin = self.election_states
+            .get(&(block.epoch, block.view))
+            .and_then(|state| state.lock().unwrap().coin.clone())
+            .ok_or(ConsensusError::MissingBeacon(block.epoch))?;
+        let signature = coin.signature(&self.committee, &self.pk_set)?;
+        Ok(Beacon::new(coin.epoch, coin.view, &signature))
+    }
+
     async fn output(&mut self, block: &Block) -> ConsensusResult<()> {
+        // Persist the beacon so that applications can query it by epoch. The block is committed
+        // anyway if the beacon cannot be derived.
+        let beacon = match self.beacon(block) {
+            Ok(beacon) => {
+                let value = bincode::serialize(&beacon).expect("Failed to serialize beacon");
+                self.store.write(Beacon::key(block.epoch), value).await;
+                Some(beacon)
+            }
+            Err(e) => {
+                error!("Skipping beacon: {}", e);
+                None
+            }
+        };
+
+        // Persist the committed block by epoch, so that the chain can be audited.
+        let value = bincode::serialize(block).expect("Failed to serialize block");
+        self.store.write(CommittedBlock::key(block.epoch), value).await;
+        let value = bincode::serialize(&block.epoch).expect("Failed to serialize epoch");
+        self.store.write(CommittedBlock::last_key(), value).await;
+
         // Output block with payloads.
-        if let Err(e) = self.commit_channel.send(block.clone()).await {
+        self.last_commit_timestamp = self.last_commit_timestamp.max(block.timestamp);
+        let committed = CommittedBlock {
+            block: block.clone(),
+            beacon,
+            timestamp: self.last_commit_timestamp,
+        };
+        if let Err(e) = self.commit_channel.send(committed).await {
             panic!("Failed to send message through commit channel: {}", e);
         } else {
-            info!("Commit block {} of member {} in epoch {}, view {}", 
+            info!("Commit block {} of member {} in epoch {}, view {}, timestamp {}", 
                 block.digest(),
                 block.author,
                 block.epoch,
-                block.view,    
+                block.view,
+                self.last_commit_timestamp,
             );
         }
 
@@ -870,22 +1126,111 @@ impl Core {
             self.halt_mark += 1;
         }
 
+        // Extend the committed chain.
+        self.last_committed = block.digest();
+        self.committed.insert(block.epoch, (self.last_committed.clone(), self.last_commit_timestamp));
+        let halt_mark = self.halt_mark;
+        self.committed.retain(|&e, _| e >= halt_mark);
+        self.deferred = self.deferred.split_off(&(halt_mark + 1));
+        self.commit_replies.retain(|&(e, _), _| e > halt_mark);
+
         self.blocks_received.retain(|&(_, e, _), _| e != block.epoch);
         self.votes_aggregators.retain(|&(e, _), _| e != block.epoch);
         self.election_states.retain(|&(e, _), _| e != block.epoch);
 
+        // Delete the blocks of the epochs out of the retention depth. The committed chain is
+        // kept, under the keys of `CommittedBlock`.
+        let depth = self.parameters.retention_depth as EpochNumber;
+        if depth > 0 && block.epoch >= depth {
+            let kept = self.stored.split_off(&(block.epoch - depth + 1));
+            let obsolete = std::mem::replace(&mut self.stored, kept);
+            for key in obsolete.into_iter().flat_map(|(_, keys)| keys) {
+                self.store.delete(key).await;
+            }
+        }
+
         // Clean up payloads.
         self.mempool_driver.cleanup_async(&block).await;
 
         Ok(())
     }
 
+    // Process the blocks extending the one we just committed, and enter the next epoch.
+    async fn enter_next_epoch(&mut self, block: &Block) {
+        self.resume(block.epoch + 1).await;
+        let epoch = self.next_epoch.map_or(block.epoch + 1, |e| e.max(block.epoch + 1));
+        self.next_epoch = Some(epoch);
+        self.idle_since = Instant::now();
+    }
+
+    // Try to start the pending epoch. While the mempool has nothing for us, we stay idle unless
+    // other nodes already proposed for this epoch or we have been idle for `max_idle_delay`.
+    async fn propose_next_epoch(&mut self) -> ConsensusResult<()> {
+        let epoch = match self.next_epoch {
+            Some(epoch) => epoch,
+            None => return Ok(()),
+        };
+
+        let payload = self
+            .mempool_driver
+            .get(self.parameters.max_payload_size, epoch)
+            .await;
+
+        if payload.is_empty() {
+            let started_by_others = self.blocks_received.keys().any(|&(_, e, _)| e == epoch);
+            let heartbeat = self.idle_since.elapsed() >= Duration::from_millis(self.parameters.max_idle_delay);
+            if !started_by_others && !heartbeat {
+                self.idle = true;
+                return Ok(());
+            }
+            self.metrics.record_empty_epoch();
+            debug!("Proposing empty block for epoch {}", epoch);
+        } else if self.idle {
+            self.metrics.record_empty_epoch_avoided();
+            info!(
+                "Empty epochs avoided: {}, empty epochs proposed: {}",
+                self.metrics.empty_epochs_avoided(),
+                self.metrics.empty_epochs()
+            );
+        }
+
+        // Keep the epoch pending if we cannot make the block, e.g. when a certificate is
+        // missing: we try again with the next payloads of the mempool.
+        let block = self.generate_block(epoch, 1, Proof::Pi(Vec::new()), payload).await?;
+        self.next_epoch = None;
+        self.idle = false;
+        self.last_proposal = Instant::now();
+        self.spb(block).await
+    }
+
+    // Propose the first block after the committed chain, or leave its epoch pending if we
+    // cannot make it.
+    async fn propose_first_block(&mut self) {
+        let epoch = self.halt_mark + 1;
+        let payload = self
+            .mempool_driver
+            .get(self.parameters.max_payload_size, epoch)
+            .await;
+        let block = match self.generate_block(epoch, 1, Proof::Pi(Vec::new()), payload).await {
+            Ok(block) => block,
+            Err(e) => {
+                error!("Failed to generate the first block: {}", e);
+                self.next_epoch = Some(epoch);
+                return;
+            }
+        };
+        if let Err(e) = self.spb(block).await {
+            error!("Failed to start spb the first block: {}", e);
+        }
+    }
+
     pub async fn run(&mut self) {
         // Upon booting, generate the very first block.
-        let block = self.generate_block(1, 1, Proof::Pi(Vec::new()))
-            .await
-            .expect("Failed to generate the first block.");
-        self.spb(block).await.expect("Failed to start spb the first block.");
+        self.propose_first_block().await;
+
+        // Paces the proposals of new epochs.
+        let timer = sleep(Duration::from_millis(self.parameters.min_block_delay));
+        tokio::pin!(timer);
 
         loop {
             let result = tokio::select! {
@@ -900,9 +1245,16 @@ impl Core {
                         ConsensusMessage::RandomCoin(coin) => self.handle_random_coin(coin).await,
                         ConsensusMessage::PreVote(prevote) => self.handle_prevote(&prevote).await,
                         ConsensusMessage::Vote(vote) => self.handle_vote(vote).await,
+                        ConsensusMessage::CommitRequest(epoch, origin) => self.handle_commit_request(epoch, origin).await,
+                        ConsensusMessage::CommitReply(block, author) => self.handle_commit_reply(block, author).await,
                     }
                 },
                 Some(block) = self.advance_channel.recv() => {             
+                    // We may have committed this epoch from the blocks of peers meanwhile.
+                    if block.epoch <= self.halt_mark || self.epochs_halted.contains(&block.epoch) {
+                        continue;
+                    }
+
                     // Output and Clean up.
                     self.output(&block).await.expect(&format!("Failed to output and cleanup block {} of epoch {}", block.digest(), block.epoch));
 
@@ -910,13 +1262,21 @@ impl Core {
                     self.transmit(ConsensusMessage::Halt(block.clone()), None).await
                         .expect(&format!("Failed to forward Halt of epoch {}", block.epoch));
 
-                    // Enter new epoch.
-                    let new_block = self.generate_block(block.epoch+1, 1, Proof::Pi(Vec::new())).await
-                        .expect(&format!("Failed to generate block of epoch {}", block.epoch));
-                    self.spb(new_block).await.expect(&format!("Failed to start spb block of epoch {}", block.epoch+1));
+                    // Enter new epoch, but not earlier than `min_block_delay` after our last proposal.
+                    self.enter_next_epoch(&block).await;
+                    timer.as_mut().reset(self.last_proposal + Duration::from_millis(self.parameters.min_block_delay));
 
                     Ok(())
                 },
+                () = &mut timer, if self.next_epoch.is_some() => {
+                    let result = self.propose_next_epoch().await;
+                    if self.next_epoch.is_some() {
+                        // Still idle, poll the mempool again later.
+                        let delay = self.parameters.min_block_delay.max(IDLE_POLL_DELAY);
+                        timer.as_mut().reset(Instant::now() + Duration::from_millis(delay));
+                    }
+                    result
+                },
                 else => break,
             };
 
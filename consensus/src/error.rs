@@ -71,6 +71,15 @@ pub enum ConsensusError {
     #[error("Received vote from unknown authority {0}")]
     UnknownAuthority(PublicKey),
 
+    #[error("Received more than one availability vote from {0}")]
+    AuthorityReuseinCertificate(PublicKey),
+
+    #[error("Availability certificate of payload {0} without a quorum")]
+    CertificateRequiresQuorum(Digest),
+
+    #[error("Block {0} misses availability certificates")]
+    MissingCertificate(Digest),
+
     #[error("Received QC without a quorum")]
     QCRequiresQuorum,
 
@@ -80,6 +89,18 @@ pub enum ConsensusError {
     #[error("Received RandomCoin without a quorum")]
     RandomCoinRequiresQuorum,
 
+    #[error("Block {0} of epoch {1} does not extend the committed chain")]
+    BrokenChain(Digest, EpochNumber),
+
+    #[error("Block {0} of epoch {1} extends a block we did not commit yet")]
+    UnknownParent(Digest, EpochNumber),
+
+    #[error("Committed block of epoch {0} is missing")]
+    MissingCommittedBlock(EpochNumber),
+
+    #[error("Block {0} has an invalid timestamp {1}")]
+    InvalidTimestamp(Digest, u64),
+
     #[error("Malformed block {0}")]
     MalformedBlock(Digest),
 
@@ -94,4 +115,10 @@ pub enum ConsensusError {
 
     #[error("Invalid payload")]
     InvalidPayload,
+
+    #[error("No random coin to derive the beacon of epoch {0}")]
+    MissingBeacon(EpochNumber),
+
+    #[error("Invalid beacon of epoch {0}")]
+    InvalidBeacon(EpochNumber),
 }
@@ -5,10 +5,14 @@ mod error;
 mod messages;
 
 mod aggregator;
+mod beacon;
+mod certificate;
+mod commit;
 mod config;
 mod consensus;
 mod core;
 mod filter;
+mod metrics;
 mod synchronizer;
 mod mempool;
 
@@ -16,8 +20,12 @@ mod mempool;
 #[path = "tests/common.rs"]
 mod common;
 
+pub use crate::beacon::Beacon;
+pub use crate::certificate::Certificate;
+pub use crate::commit::{audit_chain, CommittedBlock};
 pub use crate::config::{Committee, Parameters, EpochNumber, ViewNumber};
 pub use crate::consensus::{Consensus};
 pub use crate::messages::{ConsensusMessage, Block, Proof};
 pub use crate::error::ConsensusError;
 pub use crate::mempool::{ConsensusMempoolMessage, PayloadStatus};
+pub use crate::metrics::PacingMetrics;
@@ -1,7 +1,7 @@
 use crate::config::EpochNumber;
 use crate::error::{ConsensusError, ConsensusResult};
 use crate::messages::Block;
-use crypto::Digest;
+use crypto::{Digest, Hash as _, PublicKey};
 use tokio::sync::mpsc::Sender;
 use tokio::sync::oneshot;
 
@@ -14,9 +14,13 @@ pub enum PayloadStatus {
 
 #[derive(Debug)]
 pub enum ConsensusMempoolMessage {
-    Get(usize, oneshot::Sender<Vec<Digest>>),
+    Get(usize, EpochNumber, oneshot::Sender<Vec<Digest>>),
     Verify(Box<Block>, oneshot::Sender<PayloadStatus>),
-    Cleanup(Vec<Digest>, EpochNumber),
+    Prefetch(Box<Block>),
+    // Payloads that a peer should hold, to fetch them from it as well.
+    Holders(Vec<Digest>, PublicKey),
+    // The committed payloads, the epoch and the digest of the committed block.
+    Cleanup(Vec<Digest>, EpochNumber, Digest),
 }
 
 pub struct MempoolDriver {
@@ -28,9 +32,9 @@ impl MempoolDriver {
         Self { mempool_channel }
     }
 
-    pub async fn get(&mut self, max: usize) -> Vec<Digest> {
+    pub async fn get(&mut self, max: usize, epoch: EpochNumber) -> Vec<Digest> {
         let (sender, receiver) = oneshot::channel();
-        let message = ConsensusMempoolMessage::Get(max, sender);
+        let message = ConsensusMempoolMessage::Get(max, epoch, sender);
         self.mempool_channel
             .send(message)
             .await
@@ -57,13 +61,35 @@ impl MempoolDriver {
         }
     }
 
+    // Ask the mempool to fetch the missing payload of a block, without waiting for it.
+    pub async fn prefetch(&mut self, block: Block) {
+        let message = ConsensusMempoolMessage::Prefetch(Box::new(block));
+        self.mempool_channel
+            .send(message)
+            .await
+            .expect("Failed to send message to mempool");
+    }
+
+    // Tell the mempool that a peer should hold the payload of a block: it echoed or voted
+    // for it, so it already verified (or fetched) this payload.
+    pub async fn holders(&mut self, block: &Block, holder: PublicKey) {
+        if block.payload.is_empty() {
+            return;
+        }
+        let message = ConsensusMempoolMessage::Holders(block.payload.clone(), holder);
+        self.mempool_channel
+            .send(message)
+            .await
+            .expect("Failed to send message to mempool");
+    }
+
     pub async fn cleanup_async(&mut self, block: &Block) {
         let digests = block
             .payload
             .iter()
             .cloned()
             .collect();
-        let message = ConsensusMempoolMessage::Cleanup(digests, block.epoch);
+        let message = ConsensusMempoolMessage::Cleanup(digests, block.epoch, block.digest());
         self.mempool_channel
             .send(message)
             .await
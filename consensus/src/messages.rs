@@ -1,14 +1,20 @@
+use crate::certificate::Certificate;
 use crate::config::{Committee, EpochNumber, ViewNumber};
 use crate::error::{ConsensusError, ConsensusResult};
 use crypto::{Digest, Signature, SignatureService, Hash, PublicKey};
 use ed25519_dalek::Digest as _;
 use ed25519_dalek::Sha512;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashSet, BTreeMap};
+use std::collections::{HashMap, HashSet, BTreeMap};
 use std::convert::TryInto;
 use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
 use threshold_crypto::{SignatureShare, PublicKeySet};
 
+#[cfg(test)]
+#[path = "tests/messages_tests.rs"]
+pub mod messages_tests;
+
 #[macro_export]
 macro_rules! digest {
     ($($x: expr),+) => {
@@ -22,6 +28,14 @@ macro_rules! digest {
     };
 }
 
+// Milliseconds since the UNIX epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Failed to measure time")
+        .as_millis() as u64
+}
+
 // Two types of proof associated with block
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub enum Proof {
@@ -60,6 +74,10 @@ pub enum ConsensusMessage {
     RandomCoin(RandomCoin),
     PreVote(PreVote),
     Vote(Vote),
+    // Request for the block committed in an epoch, from a node that missed it.
+    CommitRequest(EpochNumber, PublicKey),
+    // The block committed in an epoch, from the node replying to a request.
+    CommitReply(Block, PublicKey),
 }
 
 impl fmt::Display for ConsensusMessage {
@@ -76,6 +94,8 @@ impl fmt::Display for ConsensusMessage {
                 ConsensusMessage::RandomCoin(_) => "RANDOM_COIN",
                 ConsensusMessage::PreVote(_) => "PREVOTE",
                 ConsensusMessage::Vote(_) => "VOTE",
+                ConsensusMessage::CommitRequest(_, _) => "COMMIT_REQUEST",
+                ConsensusMessage::CommitReply(_, _) => "COMMIT_REPLY",
             }           
         )
     }
@@ -84,30 +104,44 @@ impl fmt::Display for ConsensusMessage {
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Block {
     pub payload: Vec<Digest>,
+    // Availability certificates of the payload digests, in the same order. They are only
+    // required when availability certificates are enabled, and are not part of the digest.
+    pub certificates: Vec<Certificate>,
     pub author: PublicKey,
+    // Digest of the last committed block known to the author, or the default digest for the first epoch.
+    pub parent: Digest,
     pub signature: Signature,
     pub epoch: EpochNumber,
     pub view: ViewNumber,
+    // Proposer's clock in milliseconds since the UNIX epoch.
+    pub timestamp: u64,
 
     // According to proof, we can tell which PBPhase this block is currently in.
     pub proof: Proof,
 }
 
 impl Block {
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         payload: Vec<Digest>, 
+        certificates: Vec<Certificate>,
         author: PublicKey,
+        parent: Digest,
         epoch: EpochNumber,
         view: ViewNumber,
+        timestamp: u64,
         proof: Proof,
         mut signature_service: SignatureService,
     ) -> Self {
         let block = Self {
             payload,
+            certificates,
             author,
+            parent,
             signature: Signature::default(),
             epoch,
             view,
+            timestamp,
             proof,
         };
         let signature = signature_service.request_signature(block.digest()).await;
@@ -118,7 +152,9 @@ impl Block {
         &self, 
         committee: &Committee, 
         halt_mark: EpochNumber, 
-        epochs_halted: &HashSet<EpochNumber>
+        epochs_halted: &HashSet<EpochNumber>,
+        committed: &HashMap<EpochNumber, (Digest, u64)>,
+        availability_certificates: bool,
     ) -> ConsensusResult<()> {
         // Discard block with halted epoch number.
         ensure!(
@@ -133,12 +169,55 @@ impl Block {
             ConsensusError::UnknownAuthority(self.author)
         );
 
-        // Check signature.
+        // Ensure every payload is certified available, so that we never wait for it.
+        if availability_certificates {
+            ensure!(
+                self.certificates.len() == self.payload.len()
+                    && self.certificates.iter().zip(&self.payload).all(|(c, d)| c.digest == *d),
+                ConsensusError::MissingCertificate(self.digest())
+            );
+            for certificate in &self.certificates {
+                certificate.verify(committee)?;
+            }
+        }
+
+        self.verify_signature()?;
+
+        // Check the block extends the block committed in the previous epoch, and is not older
+        // than it. Blocks whose parent we did not commit yet cannot be checked.
+        let (parent, timestamp) = match self.epoch {
+            1 => (Digest::default(), 0),
+            _ => committed
+                .get(&(self.epoch - 1))
+                .cloned()
+                .ok_or_else(|| ConsensusError::UnknownParent(self.digest(), self.epoch))?,
+        };
+        ensure!(
+            self.parent == parent,
+            ConsensusError::BrokenChain(self.digest(), self.epoch)
+        );
+        ensure!(
+            self.timestamp >= timestamp,
+            ConsensusError::InvalidTimestamp(self.digest(), self.timestamp)
+        );
+        Ok(())
+    }
+
+    // Reject blocks from the future, up to the tolerated clock skew. This depends on our clock,
+    // so it only applies to the proposals we vote on: decided blocks are never rejected.
+    pub fn verify_timestamp(&self, max_clock_skew: u64) -> ConsensusResult<()> {
+        ensure!(
+            self.timestamp <= now() + max_clock_skew,
+            ConsensusError::InvalidTimestamp(self.digest(), self.timestamp)
+        );
+        Ok(())
+    }
+
+    pub fn verify_signature(&self) -> ConsensusResult<()> {
         // Use the digest of block used to be sent during PBPhase1.
         let mut mocked = self.clone(); 
         mocked.proof = Proof::Pi(Vec::new());
         self.signature.verify(&mocked.digest(), &self.author)?;
-
         Ok(())
     }
 
@@ -164,8 +243,10 @@ impl Hash for Block {
     fn digest(&self) -> Digest {
         let mut hasher = Sha512::new();
         hasher.update(self.author.0);
+        hasher.update(&self.parent);
         hasher.update(self.epoch.to_le_bytes());
         hasher.update(self.view.to_le_bytes());
+        hasher.update(self.timestamp.to_le_bytes());
         self.payload.iter().for_each(|p| hasher.update(p));
         hasher.update(match &self.proof {
             Proof::Pi(_) => &[0],
@@ -179,11 +260,12 @@ impl fmt::Debug for Block {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         write!(
             f,
-            "{}: B(author {}, epoch {}, view {}, PBPhase {}, payload_len {}",
+            "{}: B(author {}, epoch {}, view {}, timestamp {}, PBPhase {}, payload_len {}",
             self.digest(),
             self.author,
             self.epoch,
             self.view,
+            self.timestamp,
             match self.proof {
                 Proof::Pi(_) => "1",
                 Proof::Sigma(_, _) => "2",
@@ -470,24 +552,41 @@ impl RandomCoin {
             ConsensusError::RandomCoinRequiresQuorum
         );
 
-        let mut sigs = BTreeMap::new();
         // Check the random shares.
         for share in &self.shares {
             share.verify(committee, pk_set, halt_mark, epochs_halted)?;
-            sigs.insert(committee.id(share.author), share.signature_share.clone());
-        }
-        if let Ok(sig) = pk_set.combine_signatures(sigs.iter()) {
-            let id = usize::from_be_bytes((&sig.to_bytes()[0..8]).try_into().unwrap()) % committee.size();
-            let mut keys: Vec<_> = committee.authorities.keys().cloned().collect();
-            keys.sort();
-            let leader = keys[id];
-            ensure!(leader == self.leader, ConsensusError::RandomCoinWithWrongLeader);
-        } else {
-            ensure!(true, ConsensusError::RandomCoinWithWrongShares);
         }
+        let sig = self.signature(committee, pk_set)?;
+        ensure!(
+            Self::elect(committee, &sig) == self.leader,
+            ConsensusError::RandomCoinWithWrongLeader
+        );
 
         Ok(())
     }
+
+    // Combine the random shares into the threshold signature behind the coin.
+    pub fn signature(
+        &self,
+        committee: &Committee,
+        pk_set: &PublicKeySet,
+    ) -> ConsensusResult<threshold_crypto::Signature> {
+        let sigs: BTreeMap<_, _> = self.shares
+            .iter()
+            .map(|share| (committee.id(share.author), &share.signature_share))
+            .collect();
+        pk_set
+            .combine_signatures(sigs)
+            .map_err(|_| ConsensusError::RandomCoinWithWrongShares)
+    }
+
+    // Use the coin to elect a leader.
+    pub fn elect(committee: &Committee, sig: &threshold_crypto::Signature) -> PublicKey {
+        let id = usize::from_be_bytes((&sig.to_bytes()[0..8]).try_into().unwrap()) % committee.size();
+        let mut keys: Vec<_> = committee.authorities.keys().cloned().collect();
+        keys.sort();
+        keys[id]
+    }
 }
 
 impl fmt::Debug for RandomCoin {
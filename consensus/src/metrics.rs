@@ -0,0 +1,30 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+// Counters of the pacing of our proposals. They are shared with the node, which can read them
+// while consensus runs.
+#[derive(Clone, Default)]
+pub struct PacingMetrics {
+    empty_epochs: Arc<AtomicU64>,
+    empty_epochs_avoided: Arc<AtomicU64>,
+}
+
+impl PacingMetrics {
+    // The epochs for which we proposed an empty block.
+    pub fn empty_epochs(&self) -> u64 {
+        self.empty_epochs.load(Ordering::Relaxed)
+    }
+
+    // The epochs for which we waited for the mempool rather than proposing an empty block.
+    pub fn empty_epochs_avoided(&self) -> u64 {
+        self.empty_epochs_avoided.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn record_empty_epoch(&self) {
+        self.empty_epochs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_empty_epoch_avoided(&self) {
+        self.empty_epochs_avoided.fetch_add(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,37 @@
+use super::*;
+use crate::common::{threshold_sign, tss};
+
+// Fixture.
+fn beacon() -> Beacon {
+    let digest = digest!(1u128.to_le_bytes(), 1u8.to_le_bytes(), "RANDOMNESS_SHARE");
+    Beacon::new(1, 1, &threshold_sign(&digest))
+}
+
+#[test]
+fn verify_beacon() {
+    assert!(beacon().verify(&tss().public_keys()).is_ok());
+}
+
+#[test]
+fn verify_tampered_beacon() {
+    let pk_set = tss().public_keys();
+
+    // The coin of another epoch or view.
+    let mut tampered = beacon();
+    tampered.epoch = 2;
+    assert!(tampered.verify(&pk_set).is_err());
+
+    let mut tampered = beacon();
+    tampered.view = 2;
+    assert!(tampered.verify(&pk_set).is_err());
+
+    // A seed not derived from the signature.
+    let mut tampered = beacon();
+    tampered.seed = Digest::default();
+    assert!(tampered.verify(&pk_set).is_err());
+
+    // A malformed signature.
+    let mut tampered = beacon();
+    tampered.signature.pop();
+    assert!(tampered.verify(&pk_set).is_err());
+}
@@ -0,0 +1,58 @@
+use super::*;
+use crate::common::{committed_block, committee, tss};
+use std::fs;
+
+// Persist a block as committed by the core.
+async fn commit(store: &mut Store, block: &Block) {
+    let value = bincode::serialize(block).unwrap();
+    store.write(CommittedBlock::key(block.epoch), value).await;
+    let value = bincode::serialize(&block.epoch).unwrap();
+    store.write(CommittedBlock::last_key(), value).await;
+}
+
+// Fixture: a store with `epochs` committed blocks chained together.
+async fn chain(path: &str, epochs: EpochNumber) -> Store {
+    let _ = fs::remove_dir_all(path);
+    let mut store = Store::new(path).unwrap();
+    let mut parent = Digest::default();
+    for epoch in 1..=epochs {
+        let block = committed_block(epoch, parent, epoch as u64);
+        commit(&mut store, &block).await;
+        parent = block.digest();
+    }
+    store
+}
+
+#[tokio::test]
+async fn audit_intact_chain() {
+    let mut store = chain(".db_test_audit_intact_chain", 3).await;
+    let result = audit_chain(&mut store, &committee(), &tss().public_keys()).await;
+    assert_eq!(result.unwrap(), 3);
+}
+
+#[tokio::test]
+async fn audit_broken_link() {
+    let mut store = chain(".db_test_audit_broken_link", 3).await;
+
+    // Replace the block of epoch 2 with one that does not extend epoch 1.
+    let fork = committed_block(2, Digest::default(), 2);
+    store
+        .write(CommittedBlock::key(2), bincode::serialize(&fork).unwrap())
+        .await;
+    let result = audit_chain(&mut store, &committee(), &tss().public_keys()).await;
+    match result {
+        Err(ConsensusError::BrokenChain(digest, 2)) => assert_eq!(digest, fork.digest()),
+        _ => panic!("Unexpected result"),
+    }
+}
+
+#[tokio::test]
+async fn audit_missing_epoch() {
+    let mut store = chain(".db_test_audit_missing_epoch", 3).await;
+    store.delete(CommittedBlock::key(2)).await;
+    let result = audit_chain(&mut store, &committee(), &tss().public_keys()).await;
+    match result {
+        Err(ConsensusError::MissingCommittedBlock(2)) => (),
+        _ => panic!("Unexpected result"),
+    }
+}
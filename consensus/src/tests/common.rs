@@ -0,0 +1,71 @@
+use crate::config::Committee;
+use crate::messages::{Block, Proof};
+use crypto::Hash as _;
+use crypto::{generate_keypair, Digest, PublicKey, SecretKey, Signature};
+use rand::rngs::StdRng;
+use rand::SeedableRng as _;
+use std::collections::BTreeMap;
+use threshold_crypto::SecretKeySet;
+
+// Fixture.
+pub fn keys() -> Vec<(PublicKey, SecretKey)> {
+    let mut rng = StdRng::from_seed([0; 32]);
+    (0..4).map(|_| generate_keypair(&mut rng)).collect()
+}
+
+// Fixture.
+pub fn committee() -> Committee {
+    Committee::new(
+        keys()
+            .into_iter()
+            .enumerate()
+            .map(|(i, (name, _))| {
+                let address = format!("127.0.0.1:{}", i).parse().unwrap();
+                (name, i, /* stake */ 1, address)
+            })
+            .collect(),
+        /* epoch */ 1,
+    )
+}
+
+// Fixture: the threshold keys of the committee, any f+1 = 2 shares sign.
+pub fn tss() -> SecretKeySet {
+    SecretKeySet::random(1, &mut StdRng::from_seed([1; 32]))
+}
+
+// Fixture.
+pub fn threshold_sign(digest: &Digest) -> threshold_crypto::Signature {
+    let sk_set = tss();
+    let shares: BTreeMap<_, _> = (0..2)
+        .map(|i| (i, sk_set.secret_key_share(i).sign(digest)))
+        .collect();
+    sk_set.public_keys().combine_signatures(&shares).unwrap()
+}
+
+// Fixture: a block as proposed by the last authority.
+pub fn block(epoch: u128, parent: Digest, timestamp: u64) -> Block {
+    let (author, secret) = keys().pop().unwrap();
+    let block = Block {
+        payload: Vec::new(),
+        certificates: Vec::new(),
+        author,
+        parent,
+        signature: Signature::default(),
+        epoch,
+        view: 1,
+        timestamp,
+        proof: Proof::Pi(Vec::new()),
+    };
+    let signature = Signature::new(&block.digest(), &secret);
+    Block { signature, ..block }
+}
+
+// Fixture: a block with sigma1 and sigma2, as committed.
+pub fn committed_block(epoch: u128, parent: Digest, timestamp: u64) -> Block {
+    let mut block = block(epoch, parent, timestamp);
+    let sigma1 = threshold_sign(&block.digest());
+    block.proof = Proof::Sigma(Some(sigma1.clone()), None);
+    let sigma2 = threshold_sign(&block.digest());
+    block.proof = Proof::Sigma(Some(sigma1), Some(sigma2));
+    block
+}
@@ -0,0 +1,208 @@
+use super::*;
+use crate::common::{block, committed_block, committee, keys, tss};
+use crate::mempool::ConsensusMempoolMessage;
+use std::fs;
+use tokio::sync::mpsc::channel;
+
+// Fixture: a core whose mempool hands out the given payloads, one per request.
+async fn core(
+    path: &str,
+    parameters: Parameters,
+    payloads: Vec<Vec<Digest>>,
+) -> (
+    Core,
+    Receiver<FilterInput>,
+    Receiver<CommittedBlock>,
+    PacingMetrics,
+) {
+    let _ = fs::remove_dir_all(path);
+    let store = Store::new(path).unwrap();
+    core_with_store(store, parameters, payloads).await
+}
+
+// Fixture: a core restarting with the given store.
+async fn core_with_store(
+    store: Store,
+    parameters: Parameters,
+    payloads: Vec<Vec<Digest>>,
+) -> (
+    Core,
+    Receiver<FilterInput>,
+    Receiver<CommittedBlock>,
+    PacingMetrics,
+) {
+    let (name, secret) = keys().pop().unwrap();
+    let sk_set = tss();
+    let signature_service = SignatureService::new(secret, sk_set.secret_key_share(3));
+
+    let (tx_mempool, mut rx_mempool) = channel(10);
+    tokio::spawn(async move {
+        let mut payloads = payloads.into_iter();
+        while let Some(message) = rx_mempool.recv().await {
+            if let ConsensusMempoolMessage::Get(_, _, sender) = message {
+                let _ = sender.send(payloads.next().unwrap_or_default());
+            }
+        }
+    });
+
+    let (tx_filter, rx_filter) = channel(10);
+    let (_tx_core, rx_core) = channel(10);
+    let (tx_commit, rx_commit) = channel(10);
+    let metrics = PacingMetrics::default();
+    let core = Core::new(
+        name,
+        committee(),
+        parameters,
+        signature_service,
+        sk_set.public_keys(),
+        store,
+        MempoolDriver::new(tx_mempool),
+        rx_core,
+        tx_filter,
+        tx_commit,
+        metrics.clone(),
+    )
+    .await;
+    (core, rx_filter, rx_commit, metrics)
+}
+
+#[tokio::test]
+async fn skip_and_resume_idle_epochs() {
+    let parameters = Parameters {
+        max_idle_delay: 60_000,
+        ..Parameters::default()
+    };
+    let payloads = vec![Vec::new(), vec![Digest::default()]];
+    let (mut core, mut rx_filter, _rx_commit, metrics) =
+        core(".db_test_skip_and_resume_idle_epochs", parameters, payloads).await;
+    core.next_epoch = Some(2);
+
+    // We stay idle while the mempool has nothing for us.
+    core.propose_next_epoch().await.unwrap();
+    assert_eq!(core.next_epoch, Some(2));
+    assert!(rx_filter.try_recv().is_err());
+
+    // And propose as soon as it has a payload.
+    core.propose_next_epoch().await.unwrap();
+    assert_eq!(core.next_epoch, None);
+    match rx_filter.recv().await {
+        Some((ConsensusMessage::Val(block), _)) => {
+            assert_eq!(block.epoch, 2);
+            assert_eq!(block.payload, vec![Digest::default()]);
+        }
+        _ => panic!("Unexpected message"),
+    }
+    assert_eq!(metrics.empty_epochs_avoided(), 1);
+    assert_eq!(metrics.empty_epochs(), 0);
+}
+
+#[tokio::test]
+async fn propose_empty_epochs_after_max_idle_delay() {
+    let parameters = Parameters {
+        max_idle_delay: 0,
+        ..Parameters::default()
+    };
+    let (mut core, mut rx_filter, _rx_commit, metrics) =
+        core(".db_test_propose_empty_epochs", parameters, Vec::new()).await;
+    core.next_epoch = Some(2);
+
+    // The heartbeat keeps the epochs going without payloads.
+    core.propose_next_epoch().await.unwrap();
+    assert_eq!(core.next_epoch, None);
+    match rx_filter.recv().await {
+        Some((ConsensusMessage::Val(block), _)) => assert!(block.payload.is_empty()),
+        _ => panic!("Unexpected message"),
+    }
+    assert_eq!(metrics.empty_epochs(), 1);
+    assert_eq!(metrics.empty_epochs_avoided(), 0);
+}
+
+#[tokio::test]
+async fn retry_epoch_without_certificate() {
+    let parameters = Parameters {
+        availability_certificates: true,
+        max_idle_delay: 0,
+        ..Parameters::default()
+    };
+    let payloads = vec![vec![Digest::default()], vec![Digest::default()], Vec::new()];
+    let (mut core, mut rx_filter, _rx_commit, _metrics) =
+        core(".db_test_retry_uncertified_epoch", parameters, payloads).await;
+
+    // We cannot make the first block without the certificate of its payload.
+    core.propose_first_block().await;
+    assert_eq!(core.next_epoch, Some(1));
+    assert!(rx_filter.try_recv().is_err());
+
+    // The epoch stays pending until we make a block.
+    assert!(core.propose_next_epoch().await.is_err());
+    assert_eq!(core.next_epoch, Some(1));
+    core.propose_next_epoch().await.unwrap();
+    assert_eq!(core.next_epoch, None);
+    match rx_filter.recv().await {
+        Some((ConsensusMessage::Val(block), _)) => assert_eq!(block.epoch, 1),
+        _ => panic!("Unexpected message"),
+    }
+}
+
+#[tokio::test]
+async fn resume_committed_chain() {
+    let path = ".db_test_resume_committed_chain";
+    let _ = fs::remove_dir_all(path);
+    let mut store = Store::new(path).unwrap();
+    let mut parent = Digest::default();
+    for epoch in 1..=2 {
+        let block = committed_block(epoch, parent, epoch as u64);
+        let value = bincode::serialize(&block).unwrap();
+        store.write(CommittedBlock::key(epoch), value).await;
+        let value = bincode::serialize(&epoch).unwrap();
+        store.write(CommittedBlock::last_key(), value).await;
+        parent = block.digest();
+    }
+
+    // The restarted core proposes on top of the last committed block.
+    let (mut core, mut rx_filter, _rx_commit, _metrics) =
+        core_with_store(store, Parameters::default(), Vec::new()).await;
+    assert_eq!(core.halt_mark, 2);
+    core.propose_first_block().await;
+    match rx_filter.recv().await {
+        Some((ConsensusMessage::Val(block), _)) => {
+            assert_eq!(block.epoch, 3);
+            assert_eq!(block.parent, parent);
+        }
+        _ => panic!("Unexpected message"),
+    }
+}
+
+#[tokio::test]
+async fn commit_missed_block_from_peers() {
+    let (mut core, mut rx_filter, mut rx_commit, _metrics) = core(
+        ".db_test_commit_missed_block_from_peers",
+        Parameters::default(),
+        Vec::new(),
+    )
+    .await;
+
+    // A block two epochs ahead of the next one tells us we missed a commit.
+    core.handle_val(block(3, Digest([1; 32]), 3)).await.unwrap();
+    match rx_filter.recv().await {
+        Some((ConsensusMessage::CommitRequest(1, origin), _)) => assert_eq!(origin, core.name),
+        _ => panic!("Unexpected message"),
+    }
+
+    // We commit the missed block once f+1 peers sent it.
+    let missed = committed_block(1, Digest::default(), 1);
+    let peers: Vec<_> = keys().into_iter().map(|(name, _)| name).collect();
+    core.handle_commit_reply(missed.clone(), peers[0])
+        .await
+        .unwrap();
+    assert_eq!(core.halt_mark, 0);
+    core.handle_commit_reply(missed.clone(), peers[1])
+        .await
+        .unwrap();
+    assert_eq!(core.halt_mark, 1);
+    assert_eq!(core.last_committed, missed.digest());
+    match rx_commit.recv().await {
+        Some(committed) => assert_eq!(committed.block.digest(), missed.digest()),
+        _ => panic!("Unexpected message"),
+    }
+}
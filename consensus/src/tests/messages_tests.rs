@@ -0,0 +1,61 @@
+use super::*;
+use crate::common::{block, committee};
+
+// Fixture.
+fn verify(block: &Block, committed: &HashMap<EpochNumber, (Digest, u64)>) -> ConsensusResult<()> {
+    block.verify(&committee(), 0, &HashSet::new(), committed, false)
+}
+
+#[test]
+fn verify_parent() {
+    let first = block(1, Digest::default(), now());
+    assert!(verify(&first, &HashMap::new()).is_ok());
+
+    let mut committed = HashMap::new();
+    committed.insert(1, (first.digest(), first.timestamp));
+    let second = block(2, first.digest(), now());
+    assert!(verify(&second, &committed).is_ok());
+
+    // A block not extending the committed chain.
+    let fork = block(2, Digest::default(), now());
+    match verify(&fork, &committed) {
+        Err(ConsensusError::BrokenChain(_, 2)) => (),
+        _ => panic!("Unexpected result"),
+    }
+}
+
+#[test]
+fn verify_unknown_parent() {
+    let third = block(3, Digest::default(), now());
+    match verify(&third, &HashMap::new()) {
+        Err(ConsensusError::UnknownParent(_, 3)) => (),
+        _ => panic!("Unexpected result"),
+    }
+}
+
+#[test]
+fn verify_timestamp_lower_bound() {
+    let first = block(1, Digest::default(), 1_000);
+    let mut committed = HashMap::new();
+    committed.insert(1, (first.digest(), first.timestamp));
+
+    // Blocks are not older than their parent.
+    assert!(verify(&block(2, first.digest(), 1_000), &committed).is_ok());
+    match verify(&block(2, first.digest(), 999), &committed) {
+        Err(ConsensusError::InvalidTimestamp(_, 999)) => (),
+        _ => panic!("Unexpected result"),
+    }
+}
+
+#[test]
+fn verify_timestamp_upper_bound() {
+    // Proposals from the future are tolerated up to the clock skew.
+    let proposal = block(1, Digest::default(), now() + 1_000);
+    assert!(proposal.verify_timestamp(5_000).is_ok());
+    assert!(proposal.verify_timestamp(0).is_err());
+
+    // But the other checks do not depend on our clock.
+    let proposal = block(1, Digest::default(), now() + 60_000);
+    assert!(proposal.verify_timestamp(5_000).is_err());
+    assert!(verify(&proposal, &HashMap::new()).is_ok());
+}
@@ -4,12 +4,45 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::net::SocketAddr;
 
-#[derive(Serialize, Deserialize)]
+// How payloads are spread across the committee: either pushed in full to every node,
+// erasure-coded so that every node receives (and echoes) one fragment, or announced in
+// batches of digests so that every node pulls only the payloads it lacks.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub enum Dissemination {
+    Push,
+    Erasure,
+    Pull,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Parameters {
     pub queue_capacity: usize,
     pub sync_retry_delay: u64,
     pub max_payload_size: usize,
     pub min_block_delay: u64,
+    pub target_latency: u64,
+    pub max_queue_age: u64,
+    pub quota_window: u64,
+    pub quota_window_bytes: usize,
+    pub quota_window_payloads: usize,
+    pub quota_storage_bytes: usize,
+    pub payload_expiry: u64,
+    pub max_pending_syncs_per_author: usize,
+    pub max_pending_syncs_per_epoch: usize,
+    pub max_sync_requests_per_author: usize,
+    pub sync_epoch_horizon: u64,
+    pub dissemination: Dissemination,
+    pub availability_certificates: bool,
+    pub announce_batch_size: usize,
+    pub announce_delay: u64,
+    pub dedup_window: u64,
+    pub status_address: Option<SocketAddr>,
+    // Not configured on its own: the node sets it to the retention depth of consensus.
+    #[serde(skip)]
+    pub retention_depth: u64,
+    pub lane_weights: Vec<usize>,
+    pub signed_transactions: bool,
 }
 
 impl Default for Parameters {
@@ -19,15 +52,45 @@ impl Default for Parameters {
             sync_retry_delay: 10_000,
             max_payload_size: 100_000,
             min_block_delay: 100,
+            target_latency: 0,
+            max_queue_age: 2_000,
+            quota_window: 1_000,
+            quota_window_bytes: 5_000_000,
+            quota_window_payloads: 1_000,
+            quota_storage_bytes: 500_000_000,
+            payload_expiry: 100,
+            max_pending_syncs_per_author: 100,
+            max_pending_syncs_per_epoch: 1_000,
+            max_sync_requests_per_author: 10_000,
+            sync_epoch_horizon: 100,
+            dissemination: Dissemination::Push,
+            availability_certificates: false,
+            announce_batch_size: 100,
+            announce_delay: 50,
+            dedup_window: 100,
+            status_address: None,
+            retention_depth: 0,
+            lane_weights: vec![1],
+            signed_transactions: false,
         }
     }
 }
 
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct WorkerAddresses {
+    pub front_address: SocketAddr,
+    pub mempool_address: SocketAddr,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Authority {
     pub name: PublicKey,
     pub front_address: SocketAddr,
     pub mempool_address: SocketAddr,
+    // The addresses of the additional mempool workers of the authority. Worker 0 uses the
+    // addresses above, worker i > 0 the (i-1)-th entry.
+    #[serde(default)]
+    pub workers: Vec<WorkerAddresses>,
 }
 
 pub type EpochNumber = u128;
@@ -48,6 +111,7 @@ impl Committee {
                         name,
                         front_address,
                         mempool_address,
+                        workers: Vec::new(),
                     };
                     (name, authority)
                 })
@@ -56,10 +120,57 @@ impl Committee {
         }
     }
 
+    // The number of mempool workers of an authority.
+    pub fn workers(&self, name: &PublicKey) -> usize {
+        self.authorities
+            .get(name)
+            .map_or(0, |x| x.workers.len() + 1)
+    }
+
+    // The committee seen by worker `index`: the workers with the same index of all the
+    // authorities talk to each other. Authorities with fewer workers are reached at their
+    // worker 0.
+    pub fn worker(&self, index: usize) -> Self {
+        let mut committee = self.clone();
+        for authority in committee.authorities.values_mut() {
+            if let Some(worker) = index.checked_sub(1).and_then(|i| authority.workers.get(i)) {
+                authority.front_address = worker.front_address;
+                authority.mempool_address = worker.mempool_address;
+            }
+            authority.workers.clear();
+        }
+        committee
+    }
+
     pub fn exists(&self, name: &PublicKey) -> bool {
         self.authorities.contains_key(name)
     }
 
+    pub fn size(&self) -> usize {
+        self.authorities.len()
+    }
+
+    // The number of nodes ensuring at least one of them is honest (f+1).
+    pub fn validity_threshold(&self) -> usize {
+        (self.size() - 1) / 3 + 1
+    }
+
+    // The number of fragments needed to rebuild an erasure-coded payload.
+    pub fn reconstruction_threshold(&self) -> usize {
+        self.validity_threshold()
+    }
+
+    // The members of the committee, in the order of the fragments they hold.
+    pub fn members(&self) -> Vec<PublicKey> {
+        let mut members: Vec<_> = self.authorities.keys().cloned().collect();
+        members.sort();
+        members
+    }
+
+    pub fn index(&self, name: &PublicKey) -> Option<usize> {
+        self.members().iter().position(|x| x == name)
+    }
+
     pub fn front_address(&self, name: &PublicKey) -> MempoolResult<SocketAddr> {
         self.authorities
             .get(name)
@@ -0,0 +1,210 @@
+use crypto::Digest;
+use log::debug;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::time::{Duration, Instant};
+
+#[cfg(test)]
+#[path = "tests/controller_tests.rs"]
+pub mod controller_tests;
+
+// Weight of the last sample in the moving average of the commit latency.
+const SMOOTHING: f64 = 0.2;
+
+// Payloads not committed after this many times the target latency are not tracked anymore.
+const STALE_FACTOR: u32 = 20;
+
+// The decisions of the controllers of an authority, shared with the node so that they can be
+// read while the mempool runs. The sizes and delay are the ones of the last adjustment of any
+// worker, and the counters add up the adjustments of all of them.
+#[derive(Clone, Default)]
+pub struct BatchingMetrics {
+    latency: Arc<AtomicU64>,
+    payload_size: Arc<AtomicU64>,
+    block_size: Arc<AtomicU64>,
+    block_delay: Arc<AtomicU64>,
+    increases: Arc<AtomicU64>,
+    decreases: Arc<AtomicU64>,
+}
+
+impl BatchingMetrics {
+    // The estimated commit latency of our payloads (in ms).
+    pub fn latency(&self) -> u64 {
+        self.latency.load(Ordering::Relaxed)
+    }
+
+    // The size at which we seal payloads (in bytes).
+    pub fn payload_size(&self) -> u64 {
+        self.payload_size.load(Ordering::Relaxed)
+    }
+
+    // The number of digests we give to consensus for each block.
+    pub fn block_size(&self) -> u64 {
+        self.block_size.load(Ordering::Relaxed)
+    }
+
+    // The delay between two payloads we seal (in ms).
+    pub fn block_delay(&self) -> u64 {
+        self.block_delay.load(Ordering::Relaxed)
+    }
+
+    // How many times the payload size grew and shrank.
+    pub fn increases(&self) -> u64 {
+        self.increases.load(Ordering::Relaxed)
+    }
+
+    pub fn decreases(&self) -> u64 {
+        self.decreases.load(Ordering::Relaxed)
+    }
+}
+
+// Tunes the size of the payloads we seal, the delay between them and the number of digests
+// we give to consensus for each block, so that the commit latency of our payloads stays
+// around a target while the queue does not build up. A target latency of zero disables the
+// controller.
+pub struct Controller {
+    target_latency: u64,
+    min_payload_size: usize,
+    max_payload_size: usize,
+    payload_size: usize,
+    min_block_delay: u64,
+    max_block_delay: u64,
+    block_delay: u64,
+    block_size: Option<usize>, // set to the largest size consensus accepts on its first request
+    latency: Option<f64>,
+    queued: HashMap<Digest, Instant>,
+    metrics: BatchingMetrics,
+}
+
+impl Controller {
+    pub fn new(
+        target_latency: u64,
+        max_payload_size: usize,
+        max_block_delay: u64,
+        metrics: BatchingMetrics,
+    ) -> Self {
+        Self {
+            target_latency,
+            min_payload_size: (max_payload_size / 16).max(1),
+            max_payload_size,
+            payload_size: max_payload_size,
+            min_block_delay: max_block_delay / 16,
+            max_block_delay,
+            block_delay: max_block_delay,
+            block_size: None,
+            latency: None,
+            queued: HashMap::new(),
+            metrics,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.target_latency > 0
+    }
+
+    pub fn payload_size(&self) -> usize {
+        self.payload_size
+    }
+
+    // The number of digests to include in the next block, given the max size (in bytes)
+    // requested by consensus. We start from the largest blocks and shrink them from there.
+    pub fn block_size(&mut self, max: usize) -> usize {
+        let cap = max / Digest::default().size();
+        match self.enabled() {
+            true => (*self.block_size.get_or_insert(cap)).min(cap),
+            false => cap,
+        }
+    }
+
+    // Start measuring the commit latency of one of our payloads, queued at `now`.
+    pub fn queued(&mut self, digest: &Digest, now: Instant) {
+        if self.enabled() {
+            self.queued.insert(digest.clone(), now);
+        }
+    }
+
+    // Update the latency estimate with the payloads of a block committed at `now`.
+    pub fn committed(&mut self, digests: &[Digest], now: Instant) {
+        for digest in digests {
+            if let Some(start) = self.queued.remove(digest) {
+                let sample = now.saturating_duration_since(start).as_millis() as f64;
+                self.latency = Some(match self.latency {
+                    Some(latency) => (1.0 - SMOOTHING) * latency + SMOOTHING * sample,
+                    None => sample,
+                });
+            }
+        }
+        let stale = Duration::from_millis(self.target_latency) * STALE_FACTOR;
+        self.queued
+            .retain(|_, start| now.saturating_duration_since(*start) < stale);
+    }
+
+    // Adjust the payload size, block delay and block size to the latest latency estimate and
+    // queue depth. Returns the new payload size and block delay if either changed.
+    pub fn adjust(&mut self, queue_depth: usize) -> Option<(usize, u64)> {
+        let latency = match (self.enabled(), self.latency) {
+            (true, Some(latency)) => latency,
+            _ => return None,
+        };
+        let over_target = latency > self.target_latency as f64;
+        let backlog = self.block_size.map_or(false, |size| queue_depth > size);
+
+        // Seal smaller payloads when we are too slow, so that transactions do not wait for a
+        // payload to fill up; grow them back slowly otherwise to amortize their overhead.
+        let payload_size = match over_target {
+            true => (self.payload_size * 3 / 4).max(self.min_payload_size),
+            false => (self.payload_size + self.max_payload_size / 16).min(self.max_payload_size),
+        };
+
+        // Seal them more often as well, unless they already pile up in the queue: consensus is
+        // then the bottleneck, and more payloads would only wait longer.
+        let block_delay = match over_target && !backlog {
+            true => (self.block_delay * 3 / 4).max(self.min_block_delay),
+            false => (self.block_delay + self.max_block_delay / 16).min(self.max_block_delay),
+        };
+
+        // Propose enough digests to drain the queue, and fewer once it is nearly empty.
+        if let Some(size) = self.block_size.as_mut() {
+            if backlog {
+                *size = (*size * 2).min(queue_depth);
+            } else if !over_target && queue_depth < *size / 4 {
+                *size = (*size / 2).max(1);
+            }
+        }
+
+        let changed = payload_size != self.payload_size || block_delay != self.block_delay;
+        if payload_size > self.payload_size {
+            self.metrics.increases.fetch_add(1, Ordering::Relaxed);
+        } else if payload_size < self.payload_size {
+            self.metrics.decreases.fetch_add(1, Ordering::Relaxed);
+        }
+        self.payload_size = payload_size;
+        self.block_delay = block_delay;
+
+        let block_size = self.block_size.unwrap_or_default();
+        let metrics = &self.metrics;
+        metrics.latency.store(latency as u64, Ordering::Relaxed);
+        metrics
+            .payload_size
+            .store(payload_size as u64, Ordering::Relaxed);
+        metrics
+            .block_size
+            .store(block_size as u64, Ordering::Relaxed);
+        metrics.block_delay.store(block_delay, Ordering::Relaxed);
+        debug!(
+            "Adaptive batching: latency {} ms (target {} ms), queue {} payloads, payload size {} B, block delay {} ms, block size {} payloads",
+            latency as u64,
+            self.target_latency,
+            queue_depth,
+            self.payload_size,
+            self.block_delay,
+            block_size
+        );
+
+        match changed {
+            true => Some((self.payload_size, self.block_delay)),
+            false => None,
+        }
+    }
+}
@@ -1,21 +1,31 @@
-use crate::config::{Committee, Parameters};
+use crate::config::{Committee, Dissemination, Parameters};
+use crate::controller::Controller;
+use crate::dedup::DedupIndex;
+use crate::erasure::{leaf, merkle_proof, merkle_root, Codec};
 use crate::error::{MempoolError, MempoolResult};
-use crate::messages::Payload;
+use crate::gc::GarbageCollector;
+use crate::messages::{transaction_digest, Fragment, Payload, Transaction};
 use crate::payload::PayloadMaker;
+use crate::queue::{queue_key, LaneQueue, QUEUE_PREFIX};
+use crate::quota::Quotas;
+use crate::receipts::Receipts;
+use crate::shared::{ExecutionState, Shared, WorkerState};
+use crate::status::{commit_key, transaction_key};
 use crate::synchronizer::Synchronizer;
-use consensus::{Block, ConsensusMempoolMessage, PayloadStatus, EpochNumber};
+use crate::validator::{payload_lane, TransactionValidator};
+use consensus::{Block, Certificate, ConsensusMempoolMessage, PayloadStatus, EpochNumber};
 use crypto::Hash as _;
-use crypto::{Digest, PublicKey};
-#[cfg(feature = "benchmark")]
-use log::info;
-use log::{error, warn};
+use crypto::{Digest, PublicKey, Signature, SignatureService};
+use log::{error, info, warn};
 use network::NetMessage;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
-#[cfg(feature = "benchmark")]
+use std::collections::HashMap;
 use std::convert::TryInto as _;
+use std::sync::{Arc, Mutex};
 use store::Store;
 use tokio::sync::mpsc::{Receiver, Sender};
+use tokio::sync::watch;
+use tokio::time::{sleep, Duration, Instant};
 
 #[cfg(test)]
 #[path = "tests/core_tests.rs"]
@@ -26,19 +36,42 @@ pub enum MempoolMessage {
     OwnPayload(Payload),
     Payload(Payload),
     PayloadRequest(Vec<Digest>, PublicKey),
+    Fragment(Fragment),
+    PayloadAck(Digest, PublicKey, Signature),
+    Announce(Vec<Digest>, PublicKey),
 }
 
 pub struct Core {
+    id: usize,
     name: PublicKey,
     committee: Committee,
     parameters: Parameters,
     store: Store,
+    signature_service: SignatureService,
     synchronizer: Synchronizer,
     payload_maker: PayloadMaker,
     core_channel: Receiver<MempoolMessage>,
     consensus_channel: Receiver<ConsensusMempoolMessage>,
     network_channel: Sender<NetMessage>,
-    queue: HashSet<Digest>,
+    validator: Arc<dyn TransactionValidator>,
+    dedup: Arc<Mutex<DedupIndex>>,
+    saturation: Arc<watch::Sender<bool>>, // tells the front to throttle clients
+    saturated: bool,
+    receipts: Arc<Mutex<Receipts>>,
+    queue: LaneQueue,
+    proposed: HashMap<Digest, (PublicKey, EpochNumber)>, // digests we put in a block, not committed yet
+    controller: Controller,
+    quotas: Quotas,
+    gc: GarbageCollector,
+    epoch: EpochNumber,
+    // The fragments received for each (payload digest, Merkle root), and the epoch of the first.
+    fragments: HashMap<(Digest, Digest), (EpochNumber, HashMap<usize, Fragment>)>,
+    // The (payload digest, Merkle root) whose fragments do not rebuild the payload, and the epoch.
+    garbled: HashMap<(Digest, Digest), EpochNumber>,
+    // The availability votes gathered for our payloads that are not certified yet.
+    uncertified: HashMap<Digest, Vec<(PublicKey, Signature)>>,
+    // Digests of our payloads not announced yet.
+    announcements: Vec<Digest>,
 }
 
 impl Core {
@@ -48,30 +81,73 @@ impl Core {
         committee: Committee,
         parameters: Parameters,
         store: Store,
-        synchronizer: Synchronizer,
         payload_maker: PayloadMaker,
         core_channel: Receiver<MempoolMessage>,
         consensus_channel: Receiver<ConsensusMempoolMessage>,
         network_channel: Sender<NetMessage>,
+        shared: Shared,
+        worker: WorkerState,
     ) -> Self {
-        let queue = HashSet::with_capacity(parameters.queue_capacity);
+        let Shared {
+            signature_service,
+            synchronizer,
+            validator,
+            dedup,
+            quota_usage,
+            metrics,
+            execution,
+        } = shared;
+        let ExecutionState { progress, receipts } = execution;
+        let WorkerState { id, saturation, .. } = worker;
+        let queue = LaneQueue::new(parameters.max_queue_age, &parameters.lane_weights);
+        let controller = Controller::new(
+            parameters.target_latency,
+            parameters.max_payload_size,
+            parameters.min_block_delay,
+            metrics,
+        );
+        let quotas = Quotas::new(&parameters, quota_usage);
+        let gc = GarbageCollector::new(store.clone(), parameters.retention_depth, progress);
         Self {
+            id,
             name,
             committee,
             parameters,
             store,
+            signature_service,
             synchronizer,
             core_channel,
             consensus_channel,
             network_channel,
+            validator,
+            dedup,
+            saturation,
+            saturated: false,
+            receipts,
             queue,
+            proposed: HashMap::new(),
             payload_maker,
+            controller,
+            quotas,
+            gc,
+            epoch: 1,
+            fragments: HashMap::new(),
+            garbled: HashMap::new(),
+            uncertified: HashMap::new(),
+            announcements: Vec::new(),
         }
     }
 
-    async fn store_payload(&mut self, key: Vec<u8>, payload: &Payload) {
+    async fn store_payload(&mut self, digest: &Digest, payload: &Payload) {
         let value = bincode::serialize(payload).expect("Failed to serialize payload");
-        self.store.write(key, value).await;
+        self.store.write(digest.to_vec(), value).await;
+
+        // Index the payload holding each transaction.
+        let value = bincode::serialize(digest).expect("Failed to serialize digest");
+        for transaction in &payload.transactions {
+            let key = transaction_key(&transaction_digest(transaction));
+            self.store.write(key, value.clone()).await;
+        }
     }
 
     async fn transmit(
@@ -89,16 +165,28 @@ impl Core {
         .await
     }
 
+    fn check_capacity(&mut self, payload: &Payload) -> MempoolResult<()> {
+        if self.queue.len() + self.uncertified.len() >= self.parameters.queue_capacity {
+            // Clients may submit these transactions again.
+            self.dedup
+                .lock()
+                .unwrap()
+                .drop_transactions(&payload.transactions);
+            bail!(MempoolError::MempoolFull);
+        }
+        Ok(())
+    }
+
     async fn process_own_payload(
         &mut self,
         digest: &Digest,
         payload: Payload,
     ) -> MempoolResult<()> {
-        // Drop the transaction if our mempool is full.
-        ensure!(
-            self.queue.len() < self.parameters.queue_capacity,
-            MempoolError::MempoolFull
-        );
+        // Drop the transaction if our mempool is full, and tell the clients to back off.
+        if let Err(e) = self.check_capacity(&payload) {
+            self.receipts.lock().unwrap().dropped(&payload.transactions);
+            return Err(e);
+        }
 
         #[cfg(feature = "benchmark")]
         // NOTE: This log entry is used to compute performance.
@@ -121,25 +209,173 @@ impl Core {
         }
 
         // Store the payload.
-        self.store_payload(digest.to_vec(), &payload).await;
+        self.store_payload(digest, &payload).await;
+        self.assign_lane(digest, &payload.transactions);
+        self.dedup
+            .lock()
+            .unwrap()
+            .add_payload(digest.clone(), &payload.transactions);
+        self.receipts.lock().unwrap().accepted(&payload.transactions);
+
+        self.share(digest, payload).await
+    }
 
-        // Share the payload with all other nodes.
-        let message = MempoolMessage::Payload(payload);
+    fn assign_lane(&mut self, digest: &Digest, transactions: &[Transaction]) {
+        let lane = payload_lane(&*self.validator, transactions, self.queue.lanes());
+        self.queue.assign(digest.clone(), lane);
+    }
+
+    // Share one of our payloads with all other nodes.
+    async fn share(&mut self, digest: &Digest, payload: Payload) -> MempoolResult<()> {
+        match self.parameters.dissemination {
+            Dissemination::Push => {
+                let message = MempoolMessage::Payload(payload);
+                self.transmit(&message, None).await
+            }
+            Dissemination::Erasure => self.disperse(digest, &payload).await,
+            Dissemination::Pull => {
+                self.announcements.push(digest.clone());
+                match self.announcements.len() >= self.parameters.announce_batch_size {
+                    true => self.announce().await,
+                    false => Ok(()),
+                }
+            }
+        }
+    }
+
+    // Tell all other nodes about the payloads we created since the last announcement.
+    async fn announce(&mut self) -> MempoolResult<()> {
+        if self.announcements.is_empty() {
+            return Ok(());
+        }
+        let digests = self.announcements.drain(..).collect();
+        let message = MempoolMessage::Announce(digests, self.name);
         self.transmit(&message, None).await
     }
 
-    async fn handle_own_payload(&mut self, payload: Payload) -> MempoolResult<()> {
-        // Drop the transaction if our mempool is full.
+    // Pull the announced payloads we do not have yet.
+    async fn handle_announce(
+        &mut self,
+        digests: Vec<Digest>,
+        author: PublicKey,
+    ) -> MempoolResult<()> {
         ensure!(
-            self.queue.len() < self.parameters.queue_capacity,
-            MempoolError::MempoolFull
+            self.committee.exists(&author),
+            MempoolError::UnknownAuthority(author)
         );
+        let mut missing = Vec::new();
+        for digest in digests {
+            if self.store.read(digest.to_vec()).await?.is_none() {
+                missing.push(digest);
+            }
+        }
+        if missing.is_empty() {
+            return Ok(());
+        }
+        let message = MempoolMessage::PayloadRequest(missing, self.name);
+        self.transmit(&message, Some(&author)).await
+    }
 
-        // Otherwise, try to add the transaction to the next payload
+    // Send to every node its own erasure-coded fragment of the payload.
+    async fn disperse(&mut self, digest: &Digest, payload: &Payload) -> MempoolResult<()> {
+        let members = self.committee.members();
+        let codec = Codec::new(self.committee.reconstruction_threshold(), members.len())?;
+        let bytes = bincode::serialize(payload).expect("Failed to serialize payload");
+        let shards = codec.encode(&bytes);
+        let leaves: Vec<_> = shards.iter().map(|x| leaf(x)).collect();
+        let root = merkle_root(&leaves);
+        let signature = self
+            .signature_service
+            .request_signature(Fragment::commitment(digest, &root))
+            .await;
+
+        for (index, (member, data)) in members.iter().zip(shards.into_iter()).enumerate() {
+            if *member == self.name {
+                continue;
+            }
+            let fragment = Fragment {
+                digest: digest.clone(),
+                root: root.clone(),
+                index,
+                data,
+                proof: merkle_proof(&leaves, index),
+                author: self.name,
+                signature: signature.clone(),
+            };
+            let message = MempoolMessage::Fragment(fragment);
+            self.transmit(&message, Some(member)).await?;
+        }
+        Ok(())
+    }
+
+    async fn handle_own_payload(&mut self, payload: Payload) -> MempoolResult<()> {
+        // Try to add the transaction to the next payload
         // we will add to the queue.
         let digest = payload.digest();
         self.process_own_payload(&digest, payload).await?;
-        self.queue.insert(digest);
+        self.controller.queued(&digest, Instant::now());
+        self.enqueue_own(digest).await;
+        Ok(())
+    }
+
+    // Remember a payload is queued, so that it is queued again after a restart.
+    async fn persist_queued(&mut self, digest: &Digest, author: &PublicKey) {
+        let value = bincode::serialize(author).expect("Failed to serialize author");
+        self.store.write(queue_key(digest), value).await;
+    }
+
+    // Queue one of our payloads, or first wait for its availability certificate.
+    async fn enqueue_own(&mut self, digest: Digest) {
+        let name = self.name;
+        self.persist_queued(&digest, &name).await;
+        if !self.parameters.availability_certificates {
+            self.queue.insert(digest, self.name);
+            return;
+        }
+        let vote = self
+            .signature_service
+            .request_signature(Certificate::vote_digest(&digest))
+            .await;
+        self.uncertified.insert(digest.clone(), vec![(self.name, vote)]);
+        self.try_certify(digest).await;
+    }
+
+    // Persist the certificate of a payload and queue it once it gathered f+1 votes.
+    async fn try_certify(&mut self, digest: Digest) {
+        match self.uncertified.get(&digest) {
+            Some(votes) if votes.len() >= self.committee.validity_threshold() => (),
+            _ => return,
+        }
+        let votes = self.uncertified.remove(&digest).unwrap();
+        let certificate = Certificate {
+            digest: digest.clone(),
+            votes,
+        };
+        let value = bincode::serialize(&certificate).expect("Failed to serialize certificate");
+        self.store.write(Certificate::key(&digest), value).await;
+        self.queue.insert(digest, self.name);
+    }
+
+    async fn handle_ack(
+        &mut self,
+        digest: Digest,
+        voter: PublicKey,
+        signature: Signature,
+    ) -> MempoolResult<()> {
+        ensure!(
+            self.committee.exists(&voter),
+            MempoolError::UnknownAuthority(voter)
+        );
+        match self.uncertified.get(&digest) {
+            Some(votes) if !votes.iter().any(|(x, _)| *x == voter) => (),
+            _ => return Ok(()),
+        }
+        signature.verify(&Certificate::vote_digest(&digest), &voter)?;
+        self.uncertified
+            .get_mut(&digest)
+            .unwrap()
+            .push((voter, signature));
+        self.try_certify(digest).await;
         Ok(())
     }
 
@@ -157,18 +393,128 @@ impl Core {
             MempoolError::PayloadTooBig
         );
 
-        // Verify that the payload is correctly signed.
+        // Ignore the payloads we already have, e.g. when several peers answer a sync request,
+        // and the ones committed (and maybe collected) already.
         let digest = payload.digest();
+        if self.store.read(digest.to_vec()).await?.is_some()
+            || self.store.read(commit_key(&digest)).await?.is_some()
+        {
+            return Ok(());
+        }
+
+        // Verify that the payload is correctly signed.
         payload.signature.verify(&digest, &author)?;
 
+        // Check the transactions against the rules of the application.
+        for transaction in &payload.transactions {
+            self.validator
+                .validate(transaction)
+                .map_err(MempoolError::InvalidTransaction)?;
+        }
+
+        // Ensure the author stays within its quotas.
+        self.quotas.charge(&digest, author, payload.size(), self.epoch)?;
+        self.dedup
+            .lock()
+            .unwrap()
+            .add_payload(digest.clone(), &payload.transactions);
+
         // Store payload.
-        // TODO [issue #18]: A bad node may make us store a lot of junk. There is no
-        // limit to how many payloads they can send us, and we will store them all.
-        self.store_payload(digest.to_vec(), &payload).await;
+        self.store_payload(&digest, &payload).await;
 
-        // Add the payload to the queue.
-        self.queue.insert(digest);
-        Ok(())
+        // With availability certificates, only the author proposes its payloads: tell it we
+        // stored this one (and keep it until committed). Otherwise, add the payload to the queue.
+        if self.parameters.availability_certificates {
+            self.quotas.keep(&digest);
+            let vote = self
+                .signature_service
+                .request_signature(Certificate::vote_digest(&digest))
+                .await;
+            let message = MempoolMessage::PayloadAck(digest, self.name, vote);
+            self.transmit(&message, Some(&author)).await
+        } else {
+            self.persist_queued(&digest, &author).await;
+            self.assign_lane(&digest, &payload.transactions);
+            self.queue.insert(digest, author);
+            Ok(())
+        }
+    }
+
+    async fn handle_fragment(&mut self, fragment: Fragment) -> MempoolResult<()> {
+        // Ignore the fragments of payloads we already have.
+        if self.store.read(fragment.digest.to_vec()).await?.is_some() {
+            return Ok(());
+        }
+        fragment.verify(&self.committee)?;
+
+        // Charge the fragments we did not see yet to the author of the payload.
+        let id = (fragment.digest.clone(), fragment.root.clone());
+        if self.garbled.contains_key(&id) {
+            return Ok(());
+        }
+        let known = self
+            .fragments
+            .get(&id)
+            .map_or(false, |(_, x)| x.contains_key(&fragment.index));
+        if known {
+            return Ok(());
+        }
+        self.quotas.charge_fragment(
+            &fragment.digest,
+            fragment.author,
+            fragment.data.len(),
+            self.epoch,
+        )?;
+
+        // Store and echo our own fragment, the first time we see it.
+        if Some(fragment.index) == self.committee.index(&self.name) {
+            let key = Fragment::key(&fragment.digest);
+            if self.store.read(key.clone()).await?.is_none() {
+                let value = bincode::serialize(&fragment).expect("Failed to serialize fragment");
+                self.store.write(key, value).await;
+                let message = MempoolMessage::Fragment(fragment.clone());
+                self.transmit(&message, None).await?;
+            }
+        }
+
+        // Rebuild the payload once we have enough fragments.
+        let epoch = self.epoch;
+        self.fragments
+            .entry(id.clone())
+            .or_insert_with(|| (epoch, HashMap::new()))
+            .1
+            .insert(fragment.index, fragment);
+        self.rebuild(&id).await
+    }
+
+    // Rebuild an erasure-coded payload from the fragments we collected, if there are enough.
+    async fn rebuild(&mut self, id: &(Digest, Digest)) -> MempoolResult<()> {
+        let threshold = self.committee.reconstruction_threshold();
+        let shards: Vec<_> = match self.fragments.get(id) {
+            Some((_, fragments)) if fragments.len() >= threshold => fragments
+                .iter()
+                .map(|(index, x)| (*index, x.data.clone()))
+                .collect(),
+            _ => return Ok(()),
+        };
+        let codec = Codec::new(threshold, self.committee.size())?;
+        let payload: Option<Payload> = codec
+            .decode(&shards)
+            .and_then(|bytes| bincode::deserialize(&bytes).ok());
+        match payload {
+            Some(payload) if payload.digest() == id.0 => {
+                self.fragments.remove(id);
+                self.handle_others_payload(payload).await
+            }
+            _ => {
+                // We only keep fragments of the tree of the root signed by the author, so the
+                // author encoded the payload wrong: ignore the rest of its fragments.
+                let (_, fragments) = self.fragments.remove(id).unwrap();
+                let author = fragments.values().next().unwrap().author;
+                self.garbled.insert(id.clone(), self.epoch);
+                bail!(MempoolError::GarbledPayload(id.0.clone(), author))
+            }
+        }
     }
 
     async fn handle_request(
@@ -181,39 +527,183 @@ impl Core {
                 let payload = bincode::deserialize(&bytes)?;
                 let message = MempoolMessage::Payload(payload);
                 self.transmit(&message, Some(&requestor)).await?;
+            } else if let Some(bytes) = self.store.read(Fragment::key(digest)).await? {
+                // We only hold our fragment of erasure-coded payloads we did not rebuild.
+                let fragment = bincode::deserialize(&bytes)?;
+                let message = MempoolMessage::Fragment(fragment);
+                self.transmit(&message, Some(&requestor)).await?;
             }
         }
         Ok(())
     }
 
-    async fn get_payload(&mut self, max: usize) -> MempoolResult<Vec<Digest>> {
-        if self.queue.is_empty() {
+    async fn get_payload(&mut self, max: usize, epoch: EpochNumber) -> MempoolResult<Vec<Digest>> {
+        let digests = if self.queue.is_empty() {
             if let Some(payload) = self.payload_maker.make().await {
                 let digest = payload.digest();
                 self.process_own_payload(&digest, payload).await?;
-                Ok(vec![digest])
+                self.controller.queued(&digest, Instant::now());
+                if self.parameters.availability_certificates {
+                    // Not proposable before it is certified.
+                    self.enqueue_own(digest).await;
+                    Vec::new()
+                } else {
+                    // Proposed right away, but queued again after a restart until committed.
+                    let name = self.name;
+                    self.persist_queued(&digest, &name).await;
+                    vec![(digest, name)]
+                }
             } else {
-                Ok(Vec::new())
+                Vec::new()
             }
         } else {
-            let digest_len = Digest::default().size();
-            let digests = self.queue.iter().take(max / digest_len).cloned().collect();
-            for x in &digests {
-                self.queue.remove(x);
-            }
-            Ok(digests)
-        }
+            let size = self.controller.block_size(max);
+            self.queue.take(size)
+        };
+
+        // Remember what we proposed, in case our block is not the one committed.
+        Ok(digests
+            .into_iter()
+            .map(|(digest, author)| {
+                self.proposed.insert(digest.clone(), (author, epoch));
+                digest
+            })
+            .collect())
     }
 
     async fn verify_payload(&mut self, block: Box<Block>) -> MempoolResult<bool> {
+        // Try to rebuild the erasure-coded payloads of the block from the fragments we have.
+        let ids: Vec<_> = self
+            .fragments
+            .keys()
+            .filter(|(digest, _)| block.payload.contains(digest))
+            .cloned()
+            .collect();
+        for id in ids {
+            if let Err(e) = self.rebuild(&id).await {
+                warn!("{}", e);
+            }
+        }
         self.synchronizer.verify_payload(*block).await
     }
 
+    // Index the block committing each payload.
+    async fn index_commit(&mut self, digests: &[Digest], epoch: EpochNumber, block: &Digest) {
+        let value = bincode::serialize(&(epoch, block)).expect("Failed to serialize commit");
+        for digest in digests {
+            self.store.write(commit_key(digest), value.clone()).await;
+        }
+    }
+
     async fn cleanup(&mut self, digests: Vec<Digest>, epoch: EpochNumber) {
-        self.synchronizer.cleanup(epoch).await;
+        self.epoch = self.epoch.max(epoch + 1);
         for x in &digests {
             self.queue.remove(x);
+            self.proposed.remove(x);
         }
+
+        // The workers of an authority share its store, synchronizer and deduplication index:
+        // only the first one forgets the committed payloads there.
+        if self.id == 0 {
+            self.synchronizer.cleanup(epoch).await;
+            self.dedup.lock().unwrap().commit(epoch, &digests);
+            for x in &digests {
+                self.store.delete(queue_key(x)).await;
+            }
+            // Delete the committed payloads once out of the retention depth.
+            self.gc.committed(epoch, digests.clone()).await;
+        }
+
+        // Release the quotas of committed payloads, and stop proposing the ones that expired.
+        self.quotas.release(&digests);
+        let expired = self.quotas.expire(self.epoch);
+        for x in &expired {
+            self.queue.remove(x);
+            self.proposed.remove(x);
+            self.dedup.lock().unwrap().remove_payload(x);
+            self.store.delete(queue_key(x)).await;
+        }
+
+        // Delete the expired payloads now.
+        self.gc.expired(expired).await;
+
+        // Forget the fragments of payloads we could not rebuild in time.
+        let (current, expiry) = (self.epoch, self.parameters.payload_expiry as EpochNumber);
+        self.fragments.retain(|_, (e, _)| *e + expiry > current);
+        self.garbled.retain(|_, e| *e + expiry > current);
+
+        // Only the block of the elected leader is committed: the payloads we proposed in this
+        // epoch (or before) and that did not make it go back to the queue.
+        let uncommitted: Vec<_> = self.proposed
+            .iter()
+            .filter(|(_, (_, e))| *e <= epoch)
+            .map(|(x, (author, _))| (x.clone(), *author))
+            .collect();
+        for (x, author) in uncommitted {
+            self.proposed.remove(&x);
+            self.queue.insert(x, author);
+        }
+
+        // Tune payload sizes, block delay and block sizes to the observed commit latency.
+        self.controller.committed(&digests, Instant::now());
+        if let Some((size, delay)) = self.controller.adjust(self.queue.len()) {
+            self.payload_maker.tune(size, delay).await;
+        }
+    }
+
+    // Queue again the payloads persisted before a restart, unless they were committed since.
+    // The workers of an authority share its store, so only one of them recovers.
+    pub async fn recover(&mut self) -> MempoolResult<()> {
+        let mut recovered = 0;
+        for (key, value) in self.store.scan(QUEUE_PREFIX.to_vec()).await? {
+            let digest = match key[QUEUE_PREFIX.len()..].try_into() {
+                Ok(x) => Digest(x),
+                Err(_) => continue,
+            };
+            let author: PublicKey = bincode::deserialize(&value)?;
+            let payload: Payload = match self.store.read(digest.to_vec()).await? {
+                Some(bytes) if self.store.read(commit_key(&digest)).await?.is_none() => {
+                    bincode::deserialize(&bytes)?
+                }
+                _ => {
+                    self.store.delete(key).await;
+                    continue;
+                }
+            };
+
+            self.dedup
+                .lock()
+                .unwrap()
+                .add_payload(digest.clone(), &payload.transactions);
+            self.assign_lane(&digest, &payload.transactions);
+            let certified = self
+                .store
+                .read(Certificate::key(&digest))
+                .await?
+                .is_some();
+            if author == self.name && self.parameters.availability_certificates && !certified {
+                // Gather the votes again.
+                self.enqueue_own(digest.clone()).await;
+                self.share(&digest, payload).await?;
+            } else {
+                if author != self.name {
+                    // We keep the payloads we queued before restarting, even over quota.
+                    let size = payload.size();
+                    if let Err(e) = self.quotas.charge(&digest, author, size, self.epoch) {
+                        warn!("Recovering payload {} over quota: {}", digest, e);
+                    }
+                    if self.parameters.availability_certificates {
+                        self.quotas.keep(&digest);
+                    }
+                }
+                self.queue.insert(digest, author);
+            }
+            recovered += 1;
+        }
+        if recovered > 0 {
+            info!("Recovered {} queued payloads", recovered);
+        }
+        Ok(())
     }
 
     pub async fn run(&mut self) {
@@ -224,6 +714,11 @@ impl Core {
             Err(e) => warn!("{}", e),
         };
 
+        // Flushes the pending announcements.
+        let announce_delay = Duration::from_millis(self.parameters.announce_delay);
+        let timer = sleep(announce_delay);
+        tokio::pin!(timer);
+
         loop {
             let result = tokio::select! {
                 Some(message) = self.core_channel.recv() => {
@@ -231,12 +726,15 @@ impl Core {
                         MempoolMessage::OwnPayload(payload) => self.handle_own_payload(payload).await,
                         MempoolMessage::Payload(payload) => self.handle_others_payload(payload).await,
                         MempoolMessage::PayloadRequest(digest, sender) => self.handle_request(digest, sender).await,
+                        MempoolMessage::Fragment(fragment) => self.handle_fragment(fragment).await,
+                        MempoolMessage::PayloadAck(digest, voter, signature) => self.handle_ack(digest, voter, signature).await,
+                        MempoolMessage::Announce(digests, author) => self.handle_announce(digests, author).await,
                     }
                 },
                 Some(message) = self.consensus_channel.recv() => {
                     match message {
-                        ConsensusMempoolMessage::Get(max, sender) => {
-                            let result = self.get_payload(max).await;
+                        ConsensusMempoolMessage::Get(max, epoch, sender) => {
+                            let result = self.get_payload(max, epoch).await;
                             log(result.as_ref().map(|_| &()));
                             let _ = sender.send(result.unwrap_or_default());
                         },
@@ -250,13 +748,37 @@ impl Core {
                             };
                             let _ = sender.send(status);
                         },
-                        ConsensusMempoolMessage::Cleanup(digests, epoch) => self.cleanup(digests, epoch).await,
+                        ConsensusMempoolMessage::Prefetch(block) => {
+                            let result = self.synchronizer.prefetch(*block).await;
+                            log(result.as_ref());
+                        },
+                        ConsensusMempoolMessage::Holders(digests, holder) => {
+                            self.synchronizer.holders(digests, holder).await;
+                        },
+                        ConsensusMempoolMessage::Cleanup(digests, epoch, block) => {
+                            if self.id == 0 {
+                                self.index_commit(&digests, epoch, &block).await;
+                            }
+                            self.cleanup(digests, epoch).await
+                        },
                     }
                     Ok(())
                 },
+                () = &mut timer => {
+                    timer.as_mut().reset(Instant::now() + announce_delay);
+                    self.announce().await
+                },
                 else => break,
             };
             log(result.as_ref());
+
+            let pending = self.queue.len() + self.uncertified.len();
+            let saturated = pending >= self.parameters.queue_capacity;
+            if saturated != self.saturated {
+                self.saturated = saturated;
+                // The fronts may be gone, if we are shutting down.
+                let _ = self.saturation.send(saturated);
+            }
         }
     }
 }
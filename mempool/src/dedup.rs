@@ -0,0 +1,171 @@
+use crate::messages::{transaction_digest, Transaction};
+use consensus::EpochNumber;
+use crypto::Digest;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+#[cfg(test)]
+#[path = "tests/dedup_tests.rs"]
+pub mod dedup_tests;
+
+// Drops the transactions already committed in the last `window` epochs (or earlier in the
+// same epoch) from the committed sequence. It only depends on the committed blocks, so
+// every node filters the same transactions out; a window of zero disables it.
+pub struct CommitFilter {
+    window: EpochNumber,
+    seen: HashMap<Digest, EpochNumber>,
+    epochs: VecDeque<(EpochNumber, Vec<Digest>)>,
+}
+
+impl CommitFilter {
+    pub fn new(window: u64) -> Self {
+        Self {
+            window: window as EpochNumber,
+            seen: HashMap::new(),
+            epochs: VecDeque::new(),
+        }
+    }
+
+    pub fn contains(&self, digest: &Digest) -> bool {
+        self.seen.contains_key(digest)
+    }
+
+    // Keep the first occurrence of every transaction committed in `epoch`.
+    pub fn filter(&mut self, epoch: EpochNumber, transactions: Vec<Transaction>) -> Vec<Transaction> {
+        if self.window == 0 {
+            return transactions;
+        }
+        self.prune(epoch);
+        let mut digests = Vec::new();
+        let filtered = transactions
+            .into_iter()
+            .filter(|x| {
+                let digest = transaction_digest(x);
+                let fresh = !self.seen.contains_key(&digest) && !digests.contains(&digest);
+                if fresh {
+                    digests.push(digest);
+                }
+                fresh
+            })
+            .collect();
+        self.record(epoch, digests);
+        filtered
+    }
+
+    // Remember transactions committed in `epoch`.
+    pub fn record(&mut self, epoch: EpochNumber, digests: Vec<Digest>) {
+        if self.window == 0 {
+            return;
+        }
+        self.prune(epoch);
+        for digest in &digests {
+            self.seen.insert(digest.clone(), epoch);
+        }
+        self.epochs.push_back((epoch, digests));
+    }
+
+    // Forget the transactions committed out of the window of `epoch`.
+    fn prune(&mut self, epoch: EpochNumber) {
+        while let Some((e, _)) = self.epochs.front() {
+            if *e + self.window > epoch {
+                break;
+            }
+            let (e, digests) = self.epochs.pop_front().unwrap();
+            for digest in digests {
+                // The transaction may have been committed again since.
+                if self.seen.get(&digest) == Some(&e) {
+                    self.seen.remove(&digest);
+                }
+            }
+        }
+    }
+}
+
+// The transactions known to the mempool: submitted by our clients but not in a payload yet,
+// in payloads not committed yet, or committed in the last epochs. New client transactions
+// already in the index are rejected.
+pub struct DedupIndex {
+    unpacked: HashSet<Digest>,
+    packed: HashMap<Digest, usize>,
+    payloads: HashMap<Digest, Vec<Digest>>,
+    committed: CommitFilter,
+}
+
+impl DedupIndex {
+    pub fn new(window: u64) -> Self {
+        Self {
+            unpacked: HashSet::new(),
+            packed: HashMap::new(),
+            payloads: HashMap::new(),
+            committed: CommitFilter::new(window),
+        }
+    }
+
+    pub fn contains(&self, digest: &Digest) -> bool {
+        self.unpacked.contains(digest)
+            || self.packed.contains_key(digest)
+            || self.committed.contains(digest)
+    }
+
+    // Whether a client transaction is waiting to be put in a payload.
+    pub fn is_pending(&self, digest: &Digest) -> bool {
+        self.unpacked.contains(digest)
+    }
+
+    // Register a client transaction. Returns false if it is a duplicate.
+    pub fn submit(&mut self, digest: Digest) -> bool {
+        match self.contains(&digest) {
+            true => false,
+            false => self.unpacked.insert(digest),
+        }
+    }
+
+    // Register the transactions of a payload (ours or another node's).
+    pub fn add_payload(&mut self, payload: Digest, transactions: &[Transaction]) {
+        if self.payloads.contains_key(&payload) {
+            return;
+        }
+        let digests: Vec<_> = transactions.iter().map(transaction_digest).collect();
+        for digest in &digests {
+            self.unpacked.remove(digest);
+            *self.packed.entry(digest.clone()).or_insert(0) += 1;
+        }
+        self.payloads.insert(payload, digests);
+    }
+
+    // Forget client transactions that did not make it into a payload.
+    pub fn drop_transactions(&mut self, transactions: &[Transaction]) {
+        for transaction in transactions {
+            self.unpacked.remove(&transaction_digest(transaction));
+        }
+    }
+
+    // Forget a payload that will not be committed.
+    pub fn remove_payload(&mut self, payload: &Digest) {
+        if let Some(digests) = self.payloads.remove(payload) {
+            self.unpack(&digests);
+        }
+    }
+
+    // Move the transactions of the payloads committed in `epoch` to the committed window.
+    pub fn commit(&mut self, epoch: EpochNumber, payloads: &[Digest]) {
+        let mut committed = Vec::new();
+        for payload in payloads {
+            if let Some(digests) = self.payloads.remove(payload) {
+                self.unpack(&digests);
+                committed.extend(digests);
+            }
+        }
+        self.committed.record(epoch, committed);
+    }
+
+    fn unpack(&mut self, digests: &[Digest]) {
+        for digest in digests {
+            if let Some(count) = self.packed.get_mut(digest) {
+                *count -= 1;
+                if *count == 0 {
+                    self.packed.remove(digest);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use crate::error::{MempoolError, MempoolResult};
+use crypto::Digest;
+use ed25519_dalek::Digest as _;
+use ed25519_dalek::Sha512;
+use std::convert::TryInto;
+
+#[cfg(test)]
+#[path = "tests/erasure_tests.rs"]
+pub mod erasure_tests;
+
+// A systematic Reed-Solomon code over GF(2^8): the data is split into `k` shards, seen as
+// the evaluations at points 0..k of polynomials of degree k-1 (one per byte column), and the
+// `n` fragments are their evaluations at points 0..n. Any `k` fragments recover the data.
+// The points are field elements, so there are at most 255 fragments.
+pub struct Codec {
+    k: usize,
+    n: usize,
+    exp: [u8; 512],
+    log: [u8; 256],
+}
+
+impl Codec {
+    pub fn new(k: usize, n: usize) -> MempoolResult<Self> {
+        ensure!(
+            k > 0 && k <= n && n <= 255,
+            MempoolError::InvalidCodeParameters(k, n)
+        );
+
+        // Tables of the field with the primitive polynomial x^8 + x^4 + x^3 + x^2 + 1.
+        let mut exp = [0u8; 512];
+        let mut log = [0u8; 256];
+        let mut x: u16 = 1;
+        for (i, e) in exp.iter_mut().enumerate().take(255) {
+            *e = x as u8;
+            log[x as usize] = i as u8;
+            x <<= 1;
+            if x & 0x100 != 0 {
+                x ^= 0x11d;
+            }
+        }
+        let (low, high) = exp.split_at_mut(255);
+        high[..255].copy_from_slice(low);
+        Ok(Self { k, n, exp, log })
+    }
+
+    fn mul(&self, a: u8, b: u8) -> u8 {
+        match a == 0 || b == 0 {
+            true => 0,
+            false => self.exp[self.log[a as usize] as usize + self.log[b as usize] as usize],
+        }
+    }
+
+    fn div(&self, a: u8, b: u8) -> u8 {
+        match a == 0 {
+            true => 0,
+            false => self.exp[self.log[a as usize] as usize + 255 - self.log[b as usize] as usize],
+        }
+    }
+
+    // The Lagrange coefficients evaluating at `x` the polynomial going through `points`.
+    fn coefficients(&self, points: &[u8], x: u8) -> Vec<u8> {
+        points
+            .iter()
+            .map(|&xj| {
+                points.iter().filter(|&&xm| xm != xj).fold(1, |acc, &xm| {
+                    self.mul(acc, self.div(x ^ xm, xj ^ xm))
+                })
+            })
+            .collect()
+    }
+
+    // Evaluate at `x` the polynomials going through the shards at `points`.
+    fn interpolate(&self, points: &[u8], shards: &[&[u8]], x: u8) -> Vec<u8> {
+        let coefficients = self.coefficients(points, x);
+        let mut result = vec![0u8; shards[0].len()];
+        for (coefficient, shard) in coefficients.iter().zip(shards.iter()) {
+            for (r, s) in result.iter_mut().zip(shard.iter()) {
+                *r ^= self.mul(*coefficient, *s);
+            }
+        }
+        result
+    }
+
+    // Encode the data into `n` fragments. The data is prefixed with its length and padded.
+    pub fn encode(&self, data: &[u8]) -> Vec<Vec<u8>> {
+        let mut bytes = (data.len() as u64).to_le_bytes().to_vec();
+        bytes.extend_from_slice(data);
+        let len = (bytes.len() + self.k - 1) / self.k;
+        bytes.resize(len * self.k, 0);
+
+        let shards: Vec<&[u8]> = bytes.chunks(len).collect();
+        let points: Vec<u8> = (0..self.k as u8).collect();
+        (0..self.n)
+            .map(|i| match i < self.k {
+                true => shards[i].to_vec(),
+                false => self.interpolate(&points, &shards, i as u8),
+            })
+            .collect()
+    }
+
+    // Decode the data from (at least) `k` fragments with distinct indices.
+    pub fn decode(&self, fragments: &[(usize, Vec<u8>)]) -> Option<Vec<u8>> {
+        let mut points = Vec::new();
+        let mut shards: Vec<&[u8]> = Vec::new();
+        for (index, data) in fragments {
+            if *index < self.n && !points.contains(&(*index as u8)) {
+                points.push(*index as u8);
+                shards.push(data);
+            }
+        }
+        if points.len() < self.k || shards.iter().any(|x| x.len() != shards[0].len()) {
+            return None;
+        }
+        points.truncate(self.k);
+        shards.truncate(self.k);
+
+        let mut bytes = Vec::with_capacity(shards[0].len() * self.k);
+        for x in 0..self.k as u8 {
+            match points.iter().position(|p| *p == x) {
+                Some(i) => bytes.extend_from_slice(shards[i]),
+                None => bytes.extend(self.interpolate(&points, &shards, x)),
+            }
+        }
+
+        let length = u64::from_le_bytes(bytes.get(..8)?.try_into().ok()?) as usize;
+        bytes.get(8..8usize.checked_add(length)?).map(|x| x.to_vec())
+    }
+}
+
+fn hash(left: &[u8], right: &[u8]) -> Digest {
+    let mut hasher = Sha512::new();
+    hasher.update(left);
+    hasher.update(right);
+    Digest(hasher.finalize().as_slice()[..32].try_into().unwrap())
+}
+
+// The Merkle leaf of a fragment.
+pub fn leaf(data: &[u8]) -> Digest {
+    hash(b"LEAF", data)
+}
+
+fn levels(leaves: &[Digest]) -> Vec<Vec<Digest>> {
+    let mut levels = vec![leaves.to_vec()];
+    while levels.last().unwrap().len() > 1 {
+        let next = levels
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|x| hash(&x[0].0, &x[x.len() - 1].0))
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+pub fn merkle_root(leaves: &[Digest]) -> Digest {
+    match leaves.is_empty() {
+        true => Digest::default(),
+        false => levels(leaves).last().unwrap()[0].clone(),
+    }
+}
+
+// The siblings on the path from the leaf at `index` to the root.
+pub fn merkle_proof(leaves: &[Digest], mut index: usize) -> Vec<Digest> {
+    let levels = levels(leaves);
+    let mut proof = Vec::new();
+    for level in &levels[..levels.len() - 1] {
+        let sibling = (index ^ 1).min(level.len() - 1);
+        proof.push(level[sibling].clone());
+        index /= 2;
+    }
+    proof
+}
+
+pub fn verify_merkle_proof(root: &Digest, leaf: &Digest, mut index: usize, proof: &[Digest]) -> bool {
+    let mut current = leaf.clone();
+    for sibling in proof {
+        current = match index % 2 == 0 {
+            true => hash(&current.0, &sibling.0),
+            false => hash(&sibling.0, &current.0),
+        };
+        index /= 2;
+    }
+    current == *root
+}
@@ -1,4 +1,4 @@
-use crypto::{CryptoError, PublicKey};
+use crypto::{CryptoError, Digest, PublicKey};
 use store::StoreError;
 use thiserror::Error;
 
@@ -34,9 +34,24 @@ pub enum MempoolError {
     #[error("Serialization error: {0}")]
     SerializationError(#[from] Box<bincode::ErrorKind>),
 
+    #[error("Invalid fragment of payload {0}")]
+    InvalidFragment(Digest),
+
+    #[error("Node {1:?} signed fragments that do not rebuild payload {0}")]
+    GarbledPayload(Digest, PublicKey),
+
+    #[error("Cannot erasure-code payloads into {1} fragments, any {0} of which rebuild them")]
+    InvalidCodeParameters(usize, usize),
+
+    #[error("Invalid transaction: {0}")]
+    InvalidTransaction(String),
+
     #[error("Payload exceed max size")]
     PayloadTooBig,
 
+    #[error("Node {0:?} exceeded its payload quota")]
+    QuotaExceeded(PublicKey),
+
     #[error("Mempool full: dropping transaction")]
     MempoolFull,
 
@@ -1,23 +1,50 @@
-use crate::messages::Transaction;
+use crate::messages::{transaction_digest, Reply, SignedTransaction, Transaction};
+use crate::nonce::NonceFilter;
+use crate::shared::{ExecutionState, Shared, WorkerState};
+use bytes::Bytes;
+use futures::sink::SinkExt as _;
 use futures::stream::StreamExt as _;
 use log::{debug, warn};
 use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::mpsc::Sender;
+use tokio::sync::mpsc::{channel, Sender};
+use tokio::time::{sleep, Duration, Instant};
 use tokio_util::codec::{Framed, LengthDelimitedCodec};
 
+// A worker pauses for at most this long (in ms) before throttling transactions.
+const MAX_PAUSE: u64 = 1_000;
+
+// The number of core replies a worker buffers before dropping them.
+const REPLIES_CAPACITY: usize = 1_000;
+
 pub struct Front {
     address: SocketAddr,
     deliver: Sender<Transaction>,
+    shared: Shared,
+    worker: WorkerState,
 }
 
 impl Front {
-    pub fn new(address: SocketAddr, deliver: Sender<Transaction>) -> Self {
-        Self { address, deliver }
+    pub fn new(
+        address: SocketAddr,
+        deliver: Sender<Transaction>,
+        shared: Shared,
+        worker: WorkerState,
+    ) -> Self {
+        Self {
+            address,
+            deliver,
+            shared,
+            worker,
+        }
     }
 
     // For each incoming request, we spawn a new worker responsible to receive
-    // messages and replay them through the provided deliver channel.
+    // messages and replay them through the provided deliver channel. The worker
+    // replies to every transaction on the same connection: invalid transactions
+    // are answered right away, the others once the core queued or dropped them,
+    // and again with their receipt once they are committed.
     pub async fn run(&self) {
         let listener = TcpListener::bind(&self.address)
             .await
@@ -33,20 +60,116 @@ impl Front {
                 }
             };
             debug!("Connection established with client {}", peer);
-            Self::spawn_worker(socket, peer, self.deliver.clone()).await;
+            Self::spawn_worker(
+                socket,
+                peer,
+                self.deliver.clone(),
+                self.shared.clone(),
+                self.worker.clone(),
+            )
+            .await;
+        }
+    }
+
+    // Whether the nonce of a signed transaction exceeds the last one committed from its sender.
+    // The filter only advances on commit, so transactions dropped by the core may be sent again.
+    fn fresh_nonce(nonces: &Option<Arc<Mutex<NonceFilter>>>, transaction: &Transaction) -> bool {
+        let nonces = match nonces {
+            Some(x) => x,
+            None => return true,
+        };
+        match SignedTransaction::from_transaction(transaction) {
+            Ok(x) => nonces.lock().unwrap().fresh(&x.sender, x.nonce),
+            Err(_) => false,
         }
     }
 
-    async fn spawn_worker(socket: TcpStream, peer: SocketAddr, deliver: Sender<Transaction>) {
+    async fn spawn_worker(
+        socket: TcpStream,
+        peer: SocketAddr,
+        deliver: Sender<Transaction>,
+        shared: Shared,
+        worker: WorkerState,
+    ) {
+        let Shared {
+            validator,
+            dedup,
+            execution,
+            ..
+        } = shared;
+        let ExecutionState {
+            receipts, nonces, ..
+        } = execution;
+        let WorkerState { mut saturated, .. } = worker;
         tokio::spawn(async move {
             let mut transport = Framed::new(socket, LengthDelimitedCodec::new());
-            while let Some(frame) = transport.next().await {
-                match frame {
-                    Ok(x) => deliver.send(x.to_vec()).await.expect("Core channel closed"),
-                    Err(e) => {
-                        warn!("Failed to receive client transaction: {}", e);
-                        return;
+            let (tx_reply, mut rx_reply) = channel(REPLIES_CAPACITY);
+            let pause = sleep(Duration::from_millis(MAX_PAUSE));
+            tokio::pin!(pause);
+            let mut paused = false;
+            loop {
+                // Stop reading while the mempool is saturated, so that clients slow down, but
+                // keep sending them the replies of the core.
+                let saturated_now = *saturated.borrow();
+                if saturated_now && !paused {
+                    pause
+                        .as_mut()
+                        .reset(Instant::now() + Duration::from_millis(MAX_PAUSE));
+                }
+                paused = saturated_now;
+                let reading = !paused || pause.is_elapsed();
+
+                let reply = tokio::select! {
+                    Some(reply) = rx_reply.recv() => reply,
+                    result = saturated.changed() => match result {
+                        Ok(()) => continue,
+                        // The core is gone.
+                        Err(_) => return,
+                    },
+                    () = &mut pause, if !reading => continue,
+                    frame = transport.next(), if reading => {
+                        let transaction = match frame {
+                            Some(Ok(x)) => x.to_vec(),
+                            Some(Err(e)) => {
+                                warn!("Failed to receive client transaction: {}", e);
+                                return;
+                            }
+                            None => break,
+                        };
+
+                        if *saturated.borrow() {
+                            Reply::Throttled
+                        } else if let Err(reason) = validator.validate(&transaction) {
+                            debug!("Rejected transaction from client {}: {}", peer, reason);
+                            Reply::Rejected(reason)
+                        } else if !Self::fresh_nonce(&nonces, &transaction) {
+                            debug!("Rejected stale transaction from client {}", peer);
+                            Reply::Rejected("Stale nonce".to_string())
+                        } else {
+                            let digest = transaction_digest(&transaction);
+                            if dedup.lock().unwrap().submit(digest.clone()) {
+                                receipts
+                                    .lock()
+                                    .unwrap()
+                                    .subscribe(digest, tx_reply.clone());
+                                deliver
+                                    .send(transaction)
+                                    .await
+                                    .expect("Core channel closed");
+                                // The core replies once it queued or dropped the transaction.
+                                continue;
+                            } else {
+                                debug!("Dropped duplicate transaction from client {}", peer);
+                                Reply::Rejected("Duplicate transaction".to_string())
+                            }
+                        }
                     }
+                };
+
+                let bytes = bincode::serialize(&reply).expect("Failed to serialize reply");
+                if let Err(e) = transport.send(Bytes::from(bytes)).await {
+                    warn!("Failed to reply to client {}: {}", peer, e);
+                    return;
                 }
             }
             debug!("Connection closed by client {}", peer);
@@ -0,0 +1,166 @@
+use crate::error::MempoolResult;
+use crate::messages::{transaction_digest, Fragment, Payload};
+use crate::queue::queue_key;
+use crate::status::transaction_key;
+use consensus::{Certificate, EpochNumber};
+use crypto::Digest;
+use log::{error, info};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use store::Store;
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+#[cfg(test)]
+use tokio::sync::oneshot;
+use tokio::time::{sleep, Duration, Instant};
+
+#[cfg(test)]
+#[path = "tests/gc_tests.rs"]
+pub mod gc_tests;
+
+// How often the disk usage of the store is reported (in ms).
+const REPORT_INTERVAL: u64 = 10_000;
+
+// The last epoch reflected in the state the node persisted, shared with the node so that the
+// garbage collector does not delete the payloads it would execute again after a restart.
+#[derive(Clone, Default)]
+pub struct ExecutionProgress {
+    executed: Arc<AtomicU64>,
+}
+
+impl ExecutionProgress {
+    pub fn executed(&self) -> EpochNumber {
+        self.executed.load(Ordering::Relaxed) as EpochNumber
+    }
+
+    pub fn advance(&self, epoch: EpochNumber) {
+        self.executed.fetch_max(epoch as u64, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug)]
+enum GcMessage {
+    Committed(EpochNumber, Vec<Digest>),
+    Expired(Vec<Digest>),
+    // Answered once the previous messages are processed.
+    #[cfg(test)]
+    Flush(oneshot::Sender<()>),
+}
+
+// Deletes from the store the payloads that expired without being committed, and the ones
+// committed more than `retention_depth` epochs ago and already executed by the node (with
+// their certificate, fragment and transaction index). Only the epoch and block committing a
+// payload are kept forever, so that we do not accept it again. A depth of zero keeps the
+// committed payloads forever.
+pub struct GarbageCollector {
+    inner_channel: Sender<GcMessage>,
+}
+
+impl GarbageCollector {
+    pub fn new(mut store: Store, retention_depth: u64, progress: ExecutionProgress) -> Self {
+        let (tx_inner, mut rx_inner): (_, Receiver<GcMessage>) = channel(10000);
+        let retention_depth = retention_depth as EpochNumber;
+
+        tokio::spawn(async move {
+            let mut committed: VecDeque<(EpochNumber, Vec<Digest>)> = VecDeque::new();
+            let mut deleted = 0u64;
+
+            let timer = sleep(Duration::from_millis(REPORT_INTERVAL));
+            tokio::pin!(timer);
+            loop {
+                let result = tokio::select! {
+                    Some(message) = rx_inner.recv() => match message {
+                        GcMessage::Committed(epoch, digests) => {
+                            if retention_depth == 0 {
+                                continue;
+                            }
+                            committed.push_back((epoch, digests));
+                            let mut result = Ok(());
+                            let executed = progress.executed();
+                            while committed.front().map_or(false, |(e, _)| e + retention_depth <= epoch && *e <= executed) {
+                                let (_, digests) = committed.pop_front().unwrap();
+                                deleted += digests.len() as u64;
+                                result = result.and(Self::delete(&mut store, &digests).await);
+                            }
+                            result
+                        },
+                        GcMessage::Expired(digests) => {
+                            deleted += digests.len() as u64;
+                            Self::delete(&mut store, &digests).await
+                        },
+                        #[cfg(test)]
+                        GcMessage::Flush(sender) => {
+                            let _ = sender.send(());
+                            Ok(())
+                        },
+                    },
+                    () = &mut timer => {
+                        timer.as_mut().reset(Instant::now() + Duration::from_millis(REPORT_INTERVAL));
+                        match store.disk_usage().await {
+                            Ok(usage) => info!("Store uses {} B on disk, {} payloads collected", usage, deleted),
+                            Err(e) => error!("{}", e),
+                        }
+                        Ok(())
+                    },
+                    else => break,
+                };
+                if let Err(e) = result {
+                    error!("{}", e);
+                }
+            }
+        });
+        Self {
+            inner_channel: tx_inner,
+        }
+    }
+
+    // Delete the payloads and everything we stored about them.
+    async fn delete(store: &mut Store, digests: &[Digest]) -> MempoolResult<()> {
+        for digest in digests {
+            if let Some(bytes) = store.read(digest.to_vec()).await? {
+                // The transactions may since be in another payload.
+                let payload: Payload = bincode::deserialize(&bytes)?;
+                let value = bincode::serialize(digest).expect("Failed to serialize digest");
+                for transaction in &payload.transactions {
+                    let key = transaction_key(&transaction_digest(transaction));
+                    if store.read(key.clone()).await?.as_ref() == Some(&value) {
+                        store.delete(key).await;
+                    }
+                }
+            }
+            store.delete(digest.to_vec()).await;
+            store.delete(Certificate::key(digest)).await;
+            store.delete(Fragment::key(digest)).await;
+            store.delete(queue_key(digest)).await;
+        }
+        Ok(())
+    }
+
+    pub async fn committed(&mut self, epoch: EpochNumber, digests: Vec<Digest>) {
+        let message = GcMessage::Committed(epoch, digests);
+        if let Err(e) = self.inner_channel.send(message).await {
+            panic!("Failed to send message to garbage collector: {}", e);
+        }
+    }
+
+    pub async fn expired(&mut self, digests: Vec<Digest>) {
+        if digests.is_empty() {
+            return;
+        }
+        let message = GcMessage::Expired(digests);
+        if let Err(e) = self.inner_channel.send(message).await {
+            panic!("Failed to send message to garbage collector: {}", e);
+        }
+    }
+
+    #[cfg(test)]
+    pub async fn flush(&mut self) {
+        let (sender, receiver) = oneshot::channel();
+        if let Err(e) = self.inner_channel.send(GcMessage::Flush(sender)).await {
+            panic!("Failed to send message to garbage collector: {}", e);
+        }
+        receiver
+            .await
+            .expect("Failed to receive reply from garbage collector");
+    }
+}
@@ -1,18 +1,40 @@
 #[macro_use]
 mod error;
 mod config;
+mod controller;
 mod core;
+mod dedup;
+mod erasure;
+mod gc;
 mod front;
 mod mempool;
 mod messages;
+mod nonce;
 mod payload;
+mod queue;
+mod quota;
+mod receipts;
+mod router;
+mod shared;
+mod status;
 mod synchronizer;
+mod validator;
 
 #[cfg(test)]
 #[path = "tests/common.rs"]
 mod common;
 
-pub use crate::config::{Committee, Parameters};
+pub use crate::config::{Committee, Dissemination, Parameters};
+pub use crate::controller::BatchingMetrics;
+pub use crate::dedup::CommitFilter;
 pub use crate::error::MempoolError;
+pub use crate::gc::ExecutionProgress;
 pub use crate::mempool::Mempool;
-pub use crate::messages::Payload;
+pub use crate::messages::{
+    transaction_digest, Payload, Receipt, Reply, SignedTransaction, Transaction,
+};
+pub use crate::nonce::NonceFilter;
+pub use crate::receipts::Receipts;
+pub use crate::shared::ExecutionState;
+pub use crate::status::TransactionStatus;
+pub use crate::validator::{AcceptAll, SignedValidator, TransactionValidator};
@@ -1,12 +1,21 @@
-use crate::config::{Committee, Parameters};
+use crate::config::{Committee, Dissemination, Parameters};
+use crate::controller::BatchingMetrics;
 use crate::core::Core;
+use crate::dedup::DedupIndex;
+use crate::erasure::Codec;
 use crate::error::MempoolResult;
 use crate::front::Front;
 use crate::payload::PayloadMaker;
+use crate::quota::QuotaUsage;
+use crate::router::Router;
+use crate::shared::{ExecutionState, Shared, WorkerState};
+use crate::status::StatusService;
 use crate::synchronizer::Synchronizer;
+use crate::validator::{SignedValidator, TransactionValidator};
 use consensus::{ConsensusMempoolMessage, ConsensusMessage};
 use crypto::{PublicKey, SignatureService};
-use log::info;
+use log::{error, info};
+use std::sync::{Arc, Mutex};
 use network::{NetReceiver, NetSender};
 use store::Store;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
@@ -18,6 +27,7 @@ pub mod mempool_tests;
 pub struct Mempool;
 
 impl Mempool {
+    #[allow(clippy::too_many_arguments)]
     pub fn run(
         name: PublicKey,
         committee: Committee,
@@ -26,6 +36,9 @@ impl Mempool {
         signature_service: SignatureService,
         consensus_channel: Sender<ConsensusMessage>,
         consensus_mempool_channel: Receiver<ConsensusMempoolMessage>,
+        validator: Arc<dyn TransactionValidator>,
+        metrics: BatchingMetrics,
+        execution: ExecutionState,
     ) -> MempoolResult<()> {
         info!(
             "Mempool queue capacity set to {} payloads",
@@ -39,7 +52,159 @@ impl Mempool {
             "Mempool min block delay set to {} ms",
             parameters.min_block_delay
         );
+        info!(
+            "Mempool target latency set to {} ms",
+            parameters.target_latency
+        );
+        info!(
+            "Mempool max queue age set to {} ms",
+            parameters.max_queue_age
+        );
+        info!(
+            "Mempool quotas set to {} B and {} payloads per {} ms, {} B stored per node",
+            parameters.quota_window_bytes,
+            parameters.quota_window_payloads,
+            parameters.quota_window,
+            parameters.quota_storage_bytes
+        );
+        info!(
+            "Mempool pending syncs limited to {} per node and {} per epoch",
+            parameters.max_pending_syncs_per_author,
+            parameters.max_pending_syncs_per_epoch
+        );
+        info!(
+            "Mempool payload requests limited to {} per node and {} epochs ahead",
+            parameters.max_sync_requests_per_author,
+            parameters.sync_epoch_horizon
+        );
+        info!(
+            "Mempool dissemination set to {:?}",
+            parameters.dissemination
+        );
+        info!(
+            "Mempool announcements batched by {} digests or {} ms",
+            parameters.announce_batch_size,
+            parameters.announce_delay
+        );
+        info!(
+            "Mempool availability certificates {}",
+            if parameters.availability_certificates { "enabled" } else { "disabled" }
+        );
+        info!(
+            "Mempool dedup window set to {} epochs",
+            parameters.dedup_window
+        );
+        info!(
+            "Mempool payload expiry set to {} epochs",
+            parameters.payload_expiry
+        );
+        info!(
+            "Mempool priority lanes weighted {:?}",
+            parameters.lane_weights
+        );
+        info!(
+            "Mempool retention depth set to {} epochs",
+            parameters.retention_depth
+        );
+        match parameters.status_address {
+            Some(address) => info!("Mempool status queries served on {}", address),
+            None => info!("Mempool status queries disabled"),
+        }
+
+        info!(
+            "Mempool signed transactions {}",
+            if parameters.signed_transactions {
+                "enabled"
+            } else {
+                "disabled"
+            }
+        );
+
+        // Erasure coding gives one fragment to every node, and supports up to 255 of them.
+        if parameters.dissemination == Dissemination::Erasure {
+            Codec::new(committee.reconstruction_threshold(), committee.size())?;
+        }
+
+        let workers = committee.workers(&name).max(1);
+        info!("Mempool running {} workers", workers);
+
+        // Clients of all workers share the transaction index of the authority.
+        let dedup = Arc::new(Mutex::new(DedupIndex::new(parameters.dedup_window)));
+
+        // Clients of signed transactions must exceed the nonces they committed, at every worker:
+        // the node shares the nonces it executed.
+        let validator: Arc<dyn TransactionValidator> = match parameters.signed_transactions {
+            true => Arc::new(SignedValidator::new(validator)),
+            false => validator,
+        };
 
+        // Run the service answering transaction status queries.
+        if let Some(address) = parameters.status_address {
+            let status = StatusService::new(address, store.clone(), dedup.clone());
+            tokio::spawn(async move {
+                status.run().await;
+            });
+        }
+
+        // The workers share the quotas of the authors and the synchronizer, so that the payloads
+        // an author makes us store (or fetch) are bounded across all of them. The synchronizer
+        // fetches payloads from the first worker of the other authorities, that serve the
+        // payloads of all their workers from the store they share.
+        let quota_usage = Arc::new(Mutex::new(QuotaUsage::default()));
+        let (tx_sync_network, rx_sync_network) = channel(10000);
+        let mut sync_sender = NetSender::new(rx_sync_network);
+        tokio::spawn(async move {
+            sync_sender.run(&0).await;
+        });
+        let synchronizer = Synchronizer::new(
+            consensus_channel,
+            store.clone(),
+            name,
+            committee.worker(0),
+            tx_sync_network,
+            &parameters,
+        );
+
+        let shared = Shared {
+            signature_service,
+            synchronizer,
+            validator,
+            dedup,
+            quota_usage,
+            metrics,
+            execution,
+        };
+
+        // Run the workers, and dispatch the requests of consensus to them.
+        let mut channels = Vec::new();
+        for id in 0..workers {
+            let (tx_worker, rx_worker) = channel(10000);
+            Self::spawn_worker(
+                id,
+                name,
+                committee.worker(id),
+                parameters.clone(),
+                store.clone(),
+                rx_worker,
+                shared.clone(),
+            )?;
+            channels.push(tx_worker);
+        }
+        Router::spawn(consensus_mempool_channel, channels);
+
+        Ok(())
+    }
+
+    // Each worker has its own front, payload maker, network and core.
+    fn spawn_worker(
+        id: usize,
+        name: PublicKey,
+        committee: Committee,
+        parameters: Parameters,
+        store: Store,
+        consensus_mempool_channel: Receiver<ConsensusMempoolMessage>,
+        shared: Shared,
+    ) -> MempoolResult<()> {
         let (tx_network, rx_network) = channel(10000);
         let (tx_core, rx_core) = channel(10000);
         let (tx_client, rx_client) = channel(10000);
@@ -50,7 +215,11 @@ impl Mempool {
             x
         })?;
 
-        let front = Front::new(address, tx_client);
+        let worker = WorkerState {
+            id,
+            ..WorkerState::default()
+        };
+        let front = Front::new(address, tx_client, shared.clone(), worker.clone());
         tokio::spawn(async move {
             front.run().await;
         });
@@ -70,22 +239,14 @@ impl Mempool {
             network_sender.run(&0).await;
         });
 
-        // Build and run the synchronizer.
-        let synchronizer = Synchronizer::new(
-            consensus_channel,
-            store.clone(),
-            name,
-            committee.clone(),
-            tx_network.clone(),
-            parameters.sync_retry_delay,
-        );
-
         // Build and run the payload maker.
         let payload_maker = PayloadMaker::new(
             name,
-            signature_service,
+            shared.signature_service.clone(),
             parameters.max_payload_size,
             parameters.min_block_delay,
+            parameters.lane_weights.len().max(1),
+            shared.validator.clone(),
             rx_client,
             tx_core,
         );
@@ -96,13 +257,19 @@ impl Mempool {
             committee,
             parameters,
             store,
-            synchronizer,
             payload_maker,
             /* core_channel */ rx_core,
             consensus_mempool_channel,
             /* network_channel */ tx_network,
+            shared,
+            worker,
         );
         tokio::spawn(async move {
+            if id == 0 {
+                if let Err(e) = core.recover().await {
+                    error!("Failed to recover the queue: {}", e);
+                }
+            }
             core.run().await;
         });
 
@@ -1,4 +1,8 @@
-use crypto::{Digest, Hash, PublicKey, Signature, SignatureService};
+use crate::config::Committee;
+use crate::erasure::{leaf, verify_merkle_proof};
+use crate::error::{MempoolError, MempoolResult};
+use consensus::EpochNumber;
+use crypto::{Digest, Hash, PublicKey, SecretKey, Signature, SignatureService};
 use ed25519_dalek::Digest as _;
 use ed25519_dalek::Sha512;
 use serde::{Deserialize, Serialize};
@@ -7,6 +11,88 @@ use std::fmt;
 
 pub type Transaction = Vec<u8>;
 
+// What the front replies to every client transaction.
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
+pub enum Reply {
+    Accepted(Digest),
+    Rejected(String),
+    Throttled,
+    Committed(Receipt),
+}
+
+// Where a client transaction was committed: its position among the transactions of the block.
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
+pub struct Receipt {
+    pub transaction: Digest,
+    pub epoch: EpochNumber,
+    pub block: Digest,
+    pub position: usize,
+}
+
+// The hash identifying a transaction, whoever submits it.
+pub fn transaction_digest(transaction: &Transaction) -> Digest {
+    let mut hasher = Sha512::new();
+    hasher.update(transaction);
+    Digest(hasher.finalize().as_slice()[..32].try_into().unwrap())
+}
+
+// The optional envelope of client transactions identifying their sender, who signs the nonce
+// and the payload. The nonces of a sender must increase, so that its transactions cannot be
+// replayed or reordered.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct SignedTransaction {
+    pub sender: PublicKey,
+    pub nonce: u64,
+    pub payload: Vec<u8>,
+    pub signature: Signature,
+}
+
+impl SignedTransaction {
+    pub fn new(sender: PublicKey, nonce: u64, payload: Vec<u8>, secret: &SecretKey) -> Self {
+        let transaction = Self {
+            sender,
+            nonce,
+            payload,
+            signature: Signature::default(),
+        };
+        let signature = Signature::new(&transaction.digest(), secret);
+        Self {
+            signature,
+            ..transaction
+        }
+    }
+
+    // Open the envelope of a client transaction. Trailing bytes are rejected, so that the
+    // same envelope cannot be submitted as different transactions.
+    pub fn from_transaction(transaction: &Transaction) -> MempoolResult<Self> {
+        let signed: Self = bincode::deserialize(transaction)?;
+        ensure!(
+            bincode::serialized_size(&signed)? == transaction.len() as u64,
+            MempoolError::InvalidTransaction("Trailing bytes".to_string())
+        );
+        Ok(signed)
+    }
+
+    pub fn to_transaction(&self) -> Transaction {
+        bincode::serialize(self).expect("Failed to serialize transaction")
+    }
+
+    pub fn verify(&self) -> MempoolResult<()> {
+        self.signature.verify(&self.digest(), &self.sender)?;
+        Ok(())
+    }
+}
+
+impl Hash for SignedTransaction {
+    fn digest(&self) -> Digest {
+        let mut hasher = Sha512::new();
+        hasher.update(self.sender.0);
+        hasher.update(self.nonce.to_le_bytes());
+        hasher.update(&self.payload);
+        Digest(hasher.finalize().as_slice()[..32].try_into().unwrap())
+    }
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct Payload {
     pub transactions: Vec<Transaction>,
@@ -53,3 +139,59 @@ impl fmt::Debug for Payload {
         write!(f, "Payload({}, {})", self.digest(), self.size())
     }
 }
+
+// One erasure-coded fragment of a payload. The author signs the Merkle root of all the
+// fragments together with the payload digest, and each fragment proves its inclusion.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Fragment {
+    pub digest: Digest,
+    pub root: Digest,
+    pub index: usize,
+    pub data: Vec<u8>,
+    pub proof: Vec<Digest>,
+    pub author: PublicKey,
+    pub signature: Signature,
+}
+
+impl Fragment {
+    // What the author of the payload signs.
+    pub fn commitment(digest: &Digest, root: &Digest) -> Digest {
+        let mut hasher = Sha512::new();
+        hasher.update(digest);
+        hasher.update(root);
+        Digest(hasher.finalize().as_slice()[..32].try_into().unwrap())
+    }
+
+    // Key under which we store our own fragment of a payload.
+    pub fn key(digest: &Digest) -> Vec<u8> {
+        let mut hasher = Sha512::new();
+        hasher.update(digest);
+        hasher.update(b"FRAGMENT");
+        hasher.finalize().as_slice()[..32].to_vec()
+    }
+
+    // Check the fragment is the one at its index in the tree of the signed root.
+    pub fn verify_proof(&self) -> bool {
+        verify_merkle_proof(&self.root, &leaf(&self.data), self.index, &self.proof)
+    }
+
+    pub fn verify(&self, committee: &Committee) -> MempoolResult<()> {
+        ensure!(
+            committee.exists(&self.author),
+            MempoolError::UnknownAuthority(self.author)
+        );
+        ensure!(
+            self.index < committee.size() && self.verify_proof(),
+            MempoolError::InvalidFragment(self.digest.clone())
+        );
+        let commitment = Self::commitment(&self.digest, &self.root);
+        self.signature.verify(&commitment, &self.author)?;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Fragment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "Fragment({}, {}, {})", self.digest, self.index, self.data.len())
+    }
+}
@@ -0,0 +1,51 @@
+use crate::messages::{SignedTransaction, Transaction};
+use crypto::PublicKey;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[cfg(test)]
+#[path = "tests/nonce_tests.rs"]
+pub mod nonce_tests;
+
+// The last committed nonce of every sender of signed transactions. The nodes commit the
+// transactions of every sender in nonce order, which only depends on the committed blocks, so
+// every node drops the same replayed and reordered transactions. The front rejects the
+// transactions whose nonce does not exceed the last committed one of their sender. The node
+// persists the filter with the state of the application, as of the same epoch.
+#[derive(Default, Serialize, Deserialize)]
+pub struct NonceFilter {
+    nonces: HashMap<PublicKey, u64>,
+}
+
+impl NonceFilter {
+    // The last nonce of a sender, if any.
+    pub fn nonce(&self, sender: &PublicKey) -> Option<u64> {
+        self.nonces.get(sender).copied()
+    }
+
+    // Whether the nonce exceeds the last one of the sender.
+    pub fn fresh(&self, sender: &PublicKey, nonce: u64) -> bool {
+        self.nonce(sender).map_or(true, |last| nonce > last)
+    }
+
+    // Returns false if the nonce does not exceed the last one of the sender.
+    pub fn accept(&mut self, sender: PublicKey, nonce: u64) -> bool {
+        if !self.fresh(&sender, nonce) {
+            return false;
+        }
+        self.nonces.insert(sender, nonce);
+        true
+    }
+
+    // Keep the committed transactions that are correctly signed and whose nonce exceeds the
+    // last one of their sender.
+    pub fn filter(&mut self, transactions: Vec<Transaction>) -> Vec<Transaction> {
+        transactions
+            .into_iter()
+            .filter(|x| match SignedTransaction::from_transaction(x) {
+                Ok(signed) => signed.verify().is_ok() && self.accept(signed.sender, signed.nonce),
+                Err(_) => false,
+            })
+            .collect()
+    }
+}
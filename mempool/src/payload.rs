@@ -1,62 +1,73 @@
 use crate::core::MempoolMessage;
 use crate::messages::{Payload, Transaction};
+use crate::validator::TransactionValidator;
 use crypto::{PublicKey, SignatureService};
+use std::sync::Arc;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
 use tokio::sync::oneshot;
 use tokio::time::{sleep, Duration};
 
 struct Runner {
-    transactions: Vec<Transaction>,
-    size: usize,
+    lanes: Vec<(Vec<Transaction>, usize)>, // the transactions of each lane and their size
     max_size: usize,
     min_block_delay: u64,
     name: PublicKey,
     signature_service: SignatureService,
+    validator: Arc<dyn TransactionValidator>,
     client_channel: Receiver<Transaction>,
     core_channel: Sender<MempoolMessage>,
     request_channel: Receiver<oneshot::Sender<Payload>>,
+    tune_channel: Receiver<(usize, u64)>,
 }
 
 impl Runner {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         name: PublicKey,
         signature_service: SignatureService,
         max_size: usize,
         min_block_delay: u64,
+        lanes: usize,
+        validator: Arc<dyn TransactionValidator>,
         client_channel: Receiver<Transaction>,
         core_channel: Sender<MempoolMessage>,
         request_channel: Receiver<oneshot::Sender<Payload>>,
+        tune_channel: Receiver<(usize, u64)>,
     ) -> Self {
         Self {
-            transactions: Vec::with_capacity(max_size),
-            size: 0,
+            lanes: (0..lanes).map(|_| (Vec::new(), 0)).collect(),
             max_size,
             min_block_delay,
             name,
             signature_service,
+            validator,
             client_channel,
             core_channel,
             request_channel,
+            tune_channel,
         }
     }
 
+    // Payloads hold the transactions of a single lane.
     async fn add(&mut self, tx: Transaction) -> Option<Payload> {
+        let lane = self.validator.lane(&tx).min(self.lanes.len() - 1);
         let length = tx.len();
-        let ret = match self.size + length > self.max_size {
-            true => Some(self.make().await),
+        let ret = match self.lanes[lane].1 + length > self.max_size {
+            true => Some(self.make(lane).await),
             false => None,
         };
 
-        self.transactions.push(tx);
-        self.size += length;
+        let (transactions, size) = &mut self.lanes[lane];
+        transactions.push(tx);
+        *size += length;
         ret
     }
 
-    async fn make(&mut self) -> Payload {
-        let transactions = self.transactions.drain(..).collect();
+    async fn make(&mut self, lane: usize) -> Payload {
+        let transactions = self.lanes[lane].0.drain(..).collect();
 
         // Cleanup state.
-        self.size = 0;
+        self.lanes[lane].1 = 0;
 
         // Make a payload.
         Payload::new(transactions, self.name, self.signature_service.clone()).await
@@ -77,7 +88,13 @@ impl Runner {
                     }
                 },
                 Some(sender) = self.request_channel.recv() => {
-                    let _ = sender.send(self.make().await);
+                    // Seal the transactions of the highest priority lane.
+                    let lane = self.lanes.iter().position(|(x, _)| !x.is_empty()).unwrap_or(0);
+                    let _ = sender.send(self.make(lane).await);
+                },
+                Some((max_size, min_block_delay)) = self.tune_channel.recv() => {
+                    self.max_size = max_size;
+                    self.min_block_delay = min_block_delay;
                 },
                 else => break,
             }
@@ -87,33 +104,49 @@ impl Runner {
 
 pub struct PayloadMaker {
     request_channel: Sender<oneshot::Sender<Payload>>,
+    tune_channel: Sender<(usize, u64)>,
 }
 
 impl PayloadMaker {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         name: PublicKey,
         signature_service: SignatureService,
         max_size: usize,
         min_block_delay: u64,
+        lanes: usize,
+        validator: Arc<dyn TransactionValidator>,
         client_channel: Receiver<Transaction>,
         core_channel: Sender<MempoolMessage>,
     ) -> Self {
         let (tx_request, rx_request) = channel(10000);
+        let (tx_tune, rx_tune) = channel(10000);
         tokio::spawn(async move {
             Runner::new(
                 name,
                 signature_service,
                 max_size,
                 min_block_delay,
+                lanes,
+                validator,
                 client_channel,
                 core_channel,
                 rx_request,
+                rx_tune,
             )
             .run()
             .await;
         });
         Self {
             request_channel: tx_request,
+            tune_channel: tx_tune,
+        }
+    }
+
+    // Change the size at which the inner runner seals payloads, and the delay between them.
+    pub async fn tune(&mut self, max_size: usize, min_block_delay: u64) {
+        if let Err(e) = self.tune_channel.send((max_size, min_block_delay)).await {
+            panic!("Failed to tune payloads of the inner runner: {}", e);
         }
     }
 
@@ -0,0 +1,231 @@
+use crypto::{Digest, PublicKey};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::ops::Bound::{Excluded, Unbounded};
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+#[path = "tests/queue_tests.rs"]
+pub mod queue_tests;
+
+// The prefix of the keys under which the queued payloads (and their author) are persisted,
+// until they are committed or expire.
+pub const QUEUE_PREFIX: &[u8] = b"QUEUE";
+
+pub fn queue_key(digest: &Digest) -> Vec<u8> {
+    [QUEUE_PREFIX, &digest.0].concat()
+}
+
+// The payload digests ready to be proposed. Payloads are served round-robin across their
+// authors, so that a node flooding us cannot starve the others, and in arrival order for
+// each author. Payloads waiting for longer than `max_age` are served first, oldest first.
+//
+// Removing a digest only drops it from `entries`; the stale copies left in the FIFOs are
+// skipped when they reach the front, and compacted away if they pile up.
+pub struct PayloadQueue {
+    max_age: Duration,
+    entries: HashMap<Digest, (PublicKey, u64)>,
+    authors: BTreeMap<PublicKey, VecDeque<(u64, Digest)>>,
+    arrivals: VecDeque<(u64, Instant, Digest)>,
+    sequence: u64,
+    cursor: Option<PublicKey>,
+}
+
+impl PayloadQueue {
+    pub fn new(max_age: u64) -> Self {
+        Self {
+            max_age: Duration::from_millis(max_age),
+            entries: HashMap::new(),
+            authors: BTreeMap::new(),
+            arrivals: VecDeque::new(),
+            sequence: 0,
+            cursor: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains(&self, digest: &Digest) -> bool {
+        self.entries.contains_key(digest)
+    }
+
+    pub fn insert(&mut self, digest: Digest, author: PublicKey) -> bool {
+        if self.entries.contains_key(&digest) {
+            return false;
+        }
+        self.sequence += 1;
+        self.entries.insert(digest.clone(), (author, self.sequence));
+        self.authors
+            .entry(author)
+            .or_insert_with(VecDeque::new)
+            .push_back((self.sequence, digest.clone()));
+        self.arrivals.push_back((self.sequence, Instant::now(), digest));
+        true
+    }
+
+    pub fn remove(&mut self, digest: &Digest) -> bool {
+        let removed = self.entries.remove(digest).is_some();
+        if removed && self.arrivals.len() > 2 * self.entries.len() + 1_000 {
+            self.compact();
+        }
+        removed
+    }
+
+    // Take up to `max` digests (with their author) out of the queue.
+    pub fn take(&mut self, max: usize) -> Vec<(Digest, PublicKey)> {
+        let mut taken = Vec::new();
+
+        // Serve the payloads that waited for too long first.
+        while taken.len() < max {
+            match self.arrivals.front() {
+                Some((sequence, _, digest)) if !self.is_live(digest, *sequence) => {
+                    self.arrivals.pop_front();
+                }
+                Some((_, arrival, _)) if arrival.elapsed() >= self.max_age => {
+                    let (_, _, digest) = self.arrivals.pop_front().unwrap();
+                    let (author, _) = self.entries.remove(&digest).unwrap();
+                    taken.push((digest, author));
+                }
+                _ => break,
+            }
+        }
+
+        // Then go round-robin across authors.
+        while taken.len() < max && !self.entries.is_empty() {
+            let author = match self.next_author() {
+                Some(author) => author,
+                None => break,
+            };
+            let head = self.pop_live(&author);
+            if self.authors.get(&author).map_or(false, |x| x.is_empty()) {
+                self.authors.remove(&author);
+            }
+            if let Some(digest) = head {
+                self.entries.remove(&digest);
+                self.cursor = Some(author);
+                taken.push((digest, author));
+            }
+        }
+        taken
+    }
+
+    fn is_live(&self, digest: &Digest, sequence: u64) -> bool {
+        self.entries
+            .get(digest)
+            .map_or(false, |(_, x)| *x == sequence)
+    }
+
+    // The author following the last one served, wrapping around.
+    fn next_author(&self) -> Option<PublicKey> {
+        let next = match &self.cursor {
+            Some(cursor) => self.authors.range((Excluded(cursor), Unbounded)).next(),
+            None => None,
+        };
+        next.or_else(|| self.authors.iter().next())
+            .map(|(author, _)| *author)
+    }
+
+    // Pop the oldest live digest of an author, dropping stale ones on the way.
+    fn pop_live(&mut self, author: &PublicKey) -> Option<Digest> {
+        while let Some((sequence, digest)) = self.authors.get_mut(author)?.pop_front() {
+            if self.is_live(&digest, sequence) {
+                return Some(digest);
+            }
+        }
+        None
+    }
+
+    fn compact(&mut self) {
+        let entries = &self.entries;
+        let live = |digest: &Digest, sequence: &u64| {
+            entries.get(digest).map_or(false, |(_, x)| x == sequence)
+        };
+        self.arrivals.retain(|(sequence, _, digest)| live(digest, sequence));
+        for fifo in self.authors.values_mut() {
+            fifo.retain(|(sequence, digest)| live(digest, sequence));
+        }
+        self.authors.retain(|_, fifo| !fifo.is_empty());
+    }
+}
+
+// One `PayloadQueue` per priority lane, lane 0 first. Lanes are served in turn, each for as
+// many digests as its weight, so that latency-critical payloads are not stuck behind bulk
+// traffic while bulk traffic still gets its share.
+pub struct LaneQueue {
+    queues: Vec<PayloadQueue>,
+    weights: Vec<usize>,
+    lanes: HashMap<Digest, usize>, // lane of the payloads, until removed
+    cursor: usize,
+    credit: usize,
+}
+
+impl LaneQueue {
+    pub fn new(max_age: u64, weights: &[usize]) -> Self {
+        let weights: Vec<_> = match weights.is_empty() {
+            true => vec![1],
+            false => weights.iter().map(|x| (*x).max(1)).collect(),
+        };
+        Self {
+            queues: weights.iter().map(|_| PayloadQueue::new(max_age)).collect(),
+            credit: weights[0],
+            weights,
+            lanes: HashMap::new(),
+            cursor: 0,
+        }
+    }
+
+    pub fn lanes(&self) -> usize {
+        self.queues.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queues.iter().map(|x| x.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queues.iter().all(|x| x.is_empty())
+    }
+
+    // Set the lane of a payload before it is inserted.
+    pub fn assign(&mut self, digest: Digest, lane: usize) {
+        self.lanes.insert(digest, lane.min(self.queues.len() - 1));
+    }
+
+    // Insert a payload in its lane (the last one if it was not assigned).
+    pub fn insert(&mut self, digest: Digest, author: PublicKey) -> bool {
+        let lane = self
+            .lanes
+            .get(&digest)
+            .copied()
+            .unwrap_or(self.queues.len() - 1);
+        self.queues[lane].insert(digest, author)
+    }
+
+    pub fn remove(&mut self, digest: &Digest) -> bool {
+        match self.lanes.remove(digest) {
+            Some(lane) => self.queues[lane].remove(digest),
+            None => self.queues.iter_mut().any(|x| x.remove(digest)),
+        }
+    }
+
+    // Take up to `max` digests (with their author) out of the lanes.
+    pub fn take(&mut self, max: usize) -> Vec<(Digest, PublicKey)> {
+        let mut taken = Vec::new();
+        while taken.len() < max && !self.is_empty() {
+            if self.credit == 0 || self.queues[self.cursor].is_empty() {
+                self.cursor = (self.cursor + 1) % self.queues.len();
+                self.credit = self.weights[self.cursor];
+                continue;
+            }
+            let batch = self.queues[self.cursor].take(self.credit.min(max - taken.len()));
+            self.credit -= batch.len();
+            taken.extend(batch);
+        }
+        taken
+    }
+}
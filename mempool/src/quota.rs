@@ -0,0 +1,171 @@
+use crate::config::Parameters;
+use crate::error::{MempoolError, MempoolResult};
+use consensus::EpochNumber;
+use crypto::{Digest, PublicKey};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+#[path = "tests/quota_tests.rs"]
+pub mod quota_tests;
+
+// What an author used of its quotas.
+struct Usage {
+    stored: usize,
+    window_start: Instant,
+    window_bytes: usize,
+    window_payloads: usize,
+}
+
+// What the authors used of their quotas, shared by the workers of an authority so that an
+// author cannot multiply its quotas by the number of workers.
+#[derive(Default)]
+pub struct QuotaUsage {
+    authors: HashMap<PublicKey, Usage>,
+}
+
+impl QuotaUsage {
+    // The usage of an author, in a fresh window if the previous one is over.
+    fn get(&mut self, author: PublicKey, window: Duration) -> &mut Usage {
+        let usage = self.authors.entry(author).or_insert_with(|| Usage {
+            stored: 0,
+            window_start: Instant::now(),
+            window_bytes: 0,
+            window_payloads: 0,
+        });
+        if window.as_millis() > 0 && usage.window_start.elapsed() >= window {
+            usage.window_start = Instant::now();
+            usage.window_bytes = 0;
+            usage.window_payloads = 0;
+        }
+        usage
+    }
+}
+
+// Bounds what each author can make us store: the bytes and number of payloads they send
+// per time window, and the bytes we hold for them until their payloads are committed or
+// expire. Any limit set to zero is disabled. The payloads we vouched for never expire. Each
+// worker tracks (and expires) the payloads it stored.
+pub struct Quotas {
+    window: Duration,
+    window_bytes: usize,
+    window_payloads: usize,
+    storage_bytes: usize,
+    expiry: EpochNumber,
+    usage: Arc<Mutex<QuotaUsage>>,
+    payloads: HashMap<Digest, (PublicKey, usize, EpochNumber)>,
+    acked: HashSet<Digest>,
+}
+
+impl Quotas {
+    pub fn new(parameters: &Parameters, usage: Arc<Mutex<QuotaUsage>>) -> Self {
+        Self {
+            window: Duration::from_millis(parameters.quota_window),
+            window_bytes: parameters.quota_window_bytes,
+            window_payloads: parameters.quota_window_payloads,
+            storage_bytes: parameters.quota_storage_bytes,
+            expiry: parameters.payload_expiry as EpochNumber,
+            usage,
+            payloads: HashMap::new(),
+            acked: HashSet::new(),
+        }
+    }
+
+    // Charge a payload of `size` bytes received in `epoch` to its author, or reject it if
+    // this would exceed one of their quotas.
+    pub fn charge(
+        &mut self,
+        digest: &Digest,
+        author: PublicKey,
+        size: usize,
+        epoch: EpochNumber,
+    ) -> MempoolResult<()> {
+        if self.payloads.contains_key(digest) {
+            return Ok(());
+        }
+
+        let mut authors = self.usage.lock().unwrap();
+        let usage = authors.get(author, self.window);
+        let exceeded = (self.window_bytes > 0 && usage.window_bytes + size > self.window_bytes)
+            || (self.window_payloads > 0 && usage.window_payloads >= self.window_payloads)
+            || (self.storage_bytes > 0 && usage.stored + size > self.storage_bytes);
+        ensure!(!exceeded, MempoolError::QuotaExceeded(author));
+
+        usage.stored += size;
+        usage.window_bytes += size;
+        usage.window_payloads += 1;
+        self.payloads.insert(digest.clone(), (author, size, epoch));
+        Ok(())
+    }
+
+    // Charge a fragment of `size` bytes of an erasure-coded payload to the author of the
+    // payload. The fragments of a payload add up to a single payload, that is not charged
+    // again once rebuilt.
+    pub fn charge_fragment(
+        &mut self,
+        digest: &Digest,
+        author: PublicKey,
+        size: usize,
+        epoch: EpochNumber,
+    ) -> MempoolResult<()> {
+        let first = !self.payloads.contains_key(digest);
+        let mut authors = self.usage.lock().unwrap();
+        let usage = authors.get(author, self.window);
+        let exceeded = (self.window_bytes > 0 && usage.window_bytes + size > self.window_bytes)
+            || (first && self.window_payloads > 0 && usage.window_payloads >= self.window_payloads)
+            || (self.storage_bytes > 0 && usage.stored + size > self.storage_bytes);
+        ensure!(!exceeded, MempoolError::QuotaExceeded(author));
+
+        usage.stored += size;
+        usage.window_bytes += size;
+        if first {
+            usage.window_payloads += 1;
+        }
+        self.payloads
+            .entry(digest.clone())
+            .or_insert((author, 0, epoch))
+            .1 += size;
+        Ok(())
+    }
+
+    // Keep a payload until it is committed: we acknowledged it, so its author may build an
+    // availability certificate counting on us to serve it.
+    pub fn keep(&mut self, digest: &Digest) {
+        if self.payloads.contains_key(digest) {
+            self.acked.insert(digest.clone());
+        }
+    }
+
+    // Release the storage held by committed payloads.
+    pub fn release(&mut self, digests: &[Digest]) {
+        for digest in digests {
+            self.acked.remove(digest);
+            if let Some((author, size, _)) = self.payloads.remove(digest) {
+                self.uncharge(&author, size);
+            }
+        }
+    }
+
+    // Release the storage held by the payloads received `expiry` epochs before `epoch` and
+    // still not committed. Returns their digests.
+    pub fn expire(&mut self, epoch: EpochNumber) -> Vec<Digest> {
+        if self.expiry == 0 {
+            return Vec::new();
+        }
+        let expired: Vec<_> = self
+            .payloads
+            .iter()
+            .filter(|(digest, (_, _, e))| e + self.expiry <= epoch && !self.acked.contains(digest))
+            .map(|(digest, _)| digest.clone())
+            .collect();
+        self.release(&expired);
+        expired
+    }
+
+    fn uncharge(&mut self, author: &PublicKey, size: usize) {
+        if let Some(usage) = self.usage.lock().unwrap().authors.get_mut(author) {
+            usage.stored = usage.stored.saturating_sub(size);
+        }
+    }
+}
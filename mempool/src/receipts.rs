@@ -0,0 +1,66 @@
+use crate::messages::{transaction_digest, Receipt, Reply, Transaction};
+use consensus::EpochNumber;
+use crypto::Digest;
+use std::collections::HashMap;
+use tokio::sync::mpsc::Sender;
+
+#[cfg(test)]
+#[path = "tests/receipts_tests.rs"]
+pub mod receipts_tests;
+
+// The client connections waiting for the outcome of the transactions they submitted.
+#[derive(Default)]
+pub struct Receipts {
+    subscribers: HashMap<Digest, Sender<Reply>>,
+}
+
+impl Receipts {
+    pub fn is_empty(&self) -> bool {
+        self.subscribers.is_empty()
+    }
+
+    pub fn subscribe(&mut self, transaction: Digest, subscriber: Sender<Reply>) {
+        self.subscribers.insert(transaction, subscriber);
+    }
+
+    // Tell the subscribers that the core queued their transactions. They keep waiting for the commit.
+    pub fn accepted(&mut self, transactions: &[Transaction]) {
+        for transaction in transactions {
+            let digest = transaction_digest(transaction);
+            if let Some(subscriber) = self.subscribers.get(&digest) {
+                let _ = subscriber.try_send(Reply::Accepted(digest));
+            }
+        }
+    }
+
+    // Tell the subscribers that the core dropped their transactions because the mempool is full.
+    pub fn dropped(&mut self, transactions: &[Transaction]) {
+        for transaction in transactions {
+            let digest = transaction_digest(transaction);
+            if let Some(subscriber) = self.subscribers.remove(&digest) {
+                let _ = subscriber.try_send(Reply::Throttled);
+            }
+        }
+    }
+
+    // Notify the subscribers of the transactions of a block committed in `epoch`. The
+    // transactions are given in the order of the block.
+    pub fn notify(&mut self, epoch: EpochNumber, block: &Digest, transactions: &[Transaction]) {
+        for (position, transaction) in transactions.iter().enumerate() {
+            let digest = transaction_digest(transaction);
+            if let Some(subscriber) = self.subscribers.remove(&digest) {
+                let receipt = Receipt {
+                    transaction: digest,
+                    epoch,
+                    block: block.clone(),
+                    position,
+                };
+                // Slow clients lose their receipts rather than block the mempool.
+                let _ = subscriber.try_send(Reply::Committed(receipt));
+            }
+        }
+
+        // Forget the subscribers whose connection is closed.
+        self.subscribers.retain(|_, x| !x.is_closed());
+    }
+}
@@ -0,0 +1,79 @@
+use consensus::{ConsensusMempoolMessage, EpochNumber};
+use crypto::Digest;
+use tokio::sync::mpsc::{Receiver, Sender};
+use tokio::sync::oneshot;
+
+#[cfg(test)]
+#[path = "tests/router_tests.rs"]
+pub mod router_tests;
+
+// Dispatches the requests of consensus to the mempool workers of the authority. Blocks are
+// filled with the digests of all workers, starting from a different worker every time. A
+// digest does not tell which worker made its payload, so the other requests (verifying,
+// prefetching and locating payloads) all go to the first worker: the workers share the store
+// and the synchronizer of the authority, so it sees the payloads of all of them, and fetches
+// the missing ones from the first worker of the other authorities.
+pub struct Router {
+    consensus_channel: Receiver<ConsensusMempoolMessage>,
+    workers: Vec<Sender<ConsensusMempoolMessage>>,
+    next: usize,
+}
+
+impl Router {
+    pub fn spawn(
+        consensus_channel: Receiver<ConsensusMempoolMessage>,
+        workers: Vec<Sender<ConsensusMempoolMessage>>,
+    ) {
+        tokio::spawn(async move {
+            Self {
+                consensus_channel,
+                workers,
+                next: 0,
+            }
+            .run()
+            .await;
+        });
+    }
+
+    async fn send(worker: &Sender<ConsensusMempoolMessage>, message: ConsensusMempoolMessage) {
+        if let Err(e) = worker.send(message).await {
+            panic!("Failed to send message to mempool worker: {}", e);
+        }
+    }
+
+    async fn get(&mut self, max: usize, epoch: EpochNumber) -> Vec<Digest> {
+        let mut digests = Vec::new();
+        for i in 0..self.workers.len() {
+            if digests.len() >= max {
+                break;
+            }
+            let worker = &self.workers[(self.next + i) % self.workers.len()];
+            let (sender, receiver) = oneshot::channel();
+            let message = ConsensusMempoolMessage::Get(max - digests.len(), epoch, sender);
+            Self::send(worker, message).await;
+            digests.extend(receiver.await.unwrap_or_default());
+        }
+        self.next = (self.next + 1) % self.workers.len();
+        digests
+    }
+
+    async fn run(&mut self) {
+        while let Some(message) = self.consensus_channel.recv().await {
+            match message {
+                ConsensusMempoolMessage::Get(max, epoch, sender) => {
+                    let digests = self.get(max, epoch).await;
+                    let _ = sender.send(digests);
+                }
+                ConsensusMempoolMessage::Cleanup(digests, epoch, block) => {
+                    for worker in &self.workers {
+                        let message =
+                            ConsensusMempoolMessage::Cleanup(digests.clone(), epoch, block.clone());
+                        Self::send(worker, message).await;
+                    }
+                }
+                // The payloads of all workers are in the store we share.
+                message => Self::send(&self.workers[0], message).await,
+            }
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use crate::controller::BatchingMetrics;
+use crate::dedup::DedupIndex;
+use crate::gc::ExecutionProgress;
+use crate::nonce::NonceFilter;
+use crate::quota::QuotaUsage;
+use crate::receipts::Receipts;
+use crate::synchronizer::Synchronizer;
+use crate::validator::TransactionValidator;
+use crypto::SignatureService;
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+
+// What the workers of an authority share, so that the transactions and payloads they accept
+// do not depend on the worker a client or a peer reaches.
+#[derive(Clone)]
+pub struct Shared {
+    pub signature_service: SignatureService,
+    pub synchronizer: Synchronizer,
+    pub validator: Arc<dyn TransactionValidator>,
+    pub dedup: Arc<Mutex<DedupIndex>>,
+    pub quota_usage: Arc<Mutex<QuotaUsage>>,
+    pub metrics: BatchingMetrics,
+    pub execution: ExecutionState,
+}
+
+// What the node shares with the mempool as it executes the committed chain: how far it read
+// the committed payloads, the clients waiting for the receipts of their transactions, and the
+// last nonce it executed from every sender of signed transactions.
+#[derive(Clone, Default)]
+pub struct ExecutionState {
+    pub progress: ExecutionProgress,
+    pub receipts: Arc<Mutex<Receipts>>,
+    pub nonces: Option<Arc<Mutex<NonceFilter>>>,
+}
+
+// What the front and the core of a worker share.
+#[derive(Clone)]
+pub struct WorkerState {
+    // The index of the worker. The first one also does the work of the authority as a whole.
+    pub id: usize,
+    // The core tells the front to throttle clients while the mempool is saturated.
+    pub saturation: Arc<watch::Sender<bool>>,
+    pub saturated: watch::Receiver<bool>,
+}
+
+impl Default for WorkerState {
+    fn default() -> Self {
+        let (saturation, saturated) = watch::channel(false);
+        Self {
+            id: 0,
+            saturation: Arc::new(saturation),
+            saturated,
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use crate::dedup::DedupIndex;
+use crate::error::MempoolResult;
+use bytes::Bytes;
+use consensus::EpochNumber;
+use crypto::Digest;
+use ed25519_dalek::Digest as _;
+use ed25519_dalek::Sha512;
+use futures::sink::SinkExt as _;
+use futures::stream::StreamExt as _;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use store::Store;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::codec::{Framed, LengthDelimitedCodec};
+
+#[cfg(test)]
+#[path = "tests/status_tests.rs"]
+pub mod status_tests;
+
+// Where a transaction is in the pipeline.
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
+pub enum TransactionStatus {
+    Unknown,
+    Pending,
+    InPayload(Digest),
+    Committed { epoch: EpochNumber, block: Digest },
+}
+
+fn key(digest: &Digest, domain: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha512::new();
+    hasher.update(digest);
+    hasher.update(domain);
+    hasher.finalize().as_slice()[..32].to_vec()
+}
+
+// Key under which the digest of the payload holding a transaction is persisted.
+pub fn transaction_key(transaction: &Digest) -> Vec<u8> {
+    key(transaction, b"TRANSACTION")
+}
+
+// Key under which the epoch and block committing a payload are persisted.
+pub fn commit_key(payload: &Digest) -> Vec<u8> {
+    key(payload, b"COMMIT")
+}
+
+// Trace a transaction through the indexes of the store and the transactions of our clients
+// not in a payload yet.
+pub async fn transaction_status(
+    store: &mut Store,
+    dedup: &Mutex<DedupIndex>,
+    transaction: &Digest,
+) -> MempoolResult<TransactionStatus> {
+    if let Some(bytes) = store.read(transaction_key(transaction)).await? {
+        let payload: Digest = bincode::deserialize(&bytes)?;
+        let status = match store.read(commit_key(&payload)).await? {
+            Some(bytes) => {
+                let (epoch, block) = bincode::deserialize(&bytes)?;
+                TransactionStatus::Committed { epoch, block }
+            }
+            None => TransactionStatus::InPayload(payload),
+        };
+        return Ok(status);
+    }
+    match dedup.lock().unwrap().is_pending(transaction) {
+        true => Ok(TransactionStatus::Pending),
+        false => Ok(TransactionStatus::Unknown),
+    }
+}
+
+// Answers the status queries of local operators and clients: each request is the digest of a
+// transaction, and each reply its status.
+pub struct StatusService {
+    address: SocketAddr,
+    store: Store,
+    dedup: Arc<Mutex<DedupIndex>>,
+}
+
+impl StatusService {
+    pub fn new(address: SocketAddr, store: Store, dedup: Arc<Mutex<DedupIndex>>) -> Self {
+        Self {
+            address,
+            store,
+            dedup,
+        }
+    }
+
+    pub async fn run(&self) {
+        let listener = TcpListener::bind(&self.address)
+            .await
+            .expect("Failed to bind to TCP port");
+
+        debug!("Listening for status queries on {}", self.address);
+        loop {
+            let (socket, peer) = match listener.accept().await {
+                Ok(value) => value,
+                Err(e) => {
+                    warn!("Failed to connect with client: {}", e);
+                    continue;
+                }
+            };
+            debug!("Connection established with client {}", peer);
+            Self::spawn_worker(socket, peer, self.store.clone(), self.dedup.clone()).await;
+        }
+    }
+
+    async fn spawn_worker(
+        socket: TcpStream,
+        peer: SocketAddr,
+        mut store: Store,
+        dedup: Arc<Mutex<DedupIndex>>,
+    ) {
+        tokio::spawn(async move {
+            let mut transport = Framed::new(socket, LengthDelimitedCodec::new());
+            while let Some(frame) = transport.next().await {
+                let digest: Digest = match frame.map(|x| bincode::deserialize(&x)) {
+                    Ok(Ok(x)) => x,
+                    Ok(Err(e)) => {
+                        warn!("Failed to deserialize status query: {}", e);
+                        return;
+                    }
+                    Err(e) => {
+                        warn!("Failed to receive status query: {}", e);
+                        return;
+                    }
+                };
+                let status = match transaction_status(&mut store, &dedup, &digest).await {
+                    Ok(x) => x,
+                    Err(e) => {
+                        warn!("Failed to query the status of {}: {}", digest, e);
+                        return;
+                    }
+                };
+                let bytes = bincode::serialize(&status).expect("Failed to serialize status");
+                if let Err(e) = transport.send(Bytes::from(bytes)).await {
+                    warn!("Failed to reply to client {}: {}", peer, e);
+                    return;
+                }
+            }
+            debug!("Connection closed by client {}", peer);
+        });
+    }
+}
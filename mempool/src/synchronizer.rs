@@ -1,4 +1,4 @@
-use crate::config::Committee;
+use crate::config::{Committee, Parameters};
 use crate::core::MempoolMessage;
 use crate::error::{MempoolError, MempoolResult};
 use bytes::Bytes;
@@ -8,7 +8,7 @@ use crypto::{Digest, PublicKey};
 use futures::future::try_join_all;
 use futures::stream::futures_unordered::FuturesUnordered;
 use futures::stream::StreamExt as _;
-use log::{debug, error};
+use log::{debug, error, info};
 use network::NetMessage;
 use std::collections::{HashMap, HashSet};
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -20,16 +20,93 @@ use tokio::time::{sleep, Duration, Instant};
 #[path = "tests/synchronizer_tests.rs"]
 pub mod synchronizer_tests;
 
+// How often we check for payload requests to retry (in ms).
+const TIMER_RESOLUTION: u64 = 100;
+
+// The retry delay of a payload request doubles on every attempt, up to this many times.
+const MAX_BACKOFF_EXPONENT: u32 = 5;
+
 enum SynchronizerMessage {
-    Sync(HashSet<Digest>, Block),
+    Sync(HashSet<Digest>, Block, bool),
+    Holders(Vec<Digest>, PublicKey),
     Clean(EpochNumber),
 }
 
+// Fetches the missing payloads of blocks. The workers of an authority share one synchronizer.
+#[derive(Clone)]
 pub struct Synchronizer {
     inner_channel: Sender<SynchronizerMessage>,
     store: Store,
 }
 
+// A block waiting for its missing payloads. Dropping the cancel handler stops the waiter.
+struct Waiter {
+    epoch: EpochNumber,
+    author: PublicKey,
+    sequence: u64,
+    missing: Vec<Digest>,
+    cancel: Sender<()>,
+}
+
+// A payload we are fetching: the peers that should hold it (the authors of the blocks
+// referencing it, and the peers consensus saw echo or vote for them) are asked first, one
+// at a time, then the rest of the committee. The request is charged to the author of the
+// first block referencing it.
+struct Fetch {
+    epoch: EpochNumber,
+    author: PublicKey,
+    holders: Vec<PublicKey>,
+    asked: Vec<PublicKey>,
+    attempt: u32,
+    deadline: u128,
+}
+
+impl Fetch {
+    // The next peer to ask: the first holder we did not ask yet, so that holders learned late
+    // are asked next, then the others. We start over once we asked everyone.
+    fn peer(&mut self, name: &PublicKey, committee: &Committee) -> PublicKey {
+        let mut others: Vec<_> = committee
+            .authorities
+            .keys()
+            .filter(|x| *x != name && !self.holders.contains(x))
+            .cloned()
+            .collect();
+        others.sort();
+        let candidates: Vec<_> = self.holders.iter().chain(others.iter()).cloned().collect();
+        if candidates.iter().all(|x| self.asked.contains(x)) {
+            self.asked.clear();
+        }
+        let peer = candidates
+            .into_iter()
+            .find(|x| !self.asked.contains(x))
+            .expect("The holders of a request cannot be empty");
+        self.asked.push(peer);
+        peer
+    }
+
+    // Move on to the next peer, waiting exponentially longer for it to reply.
+    fn retry(&mut self, now: u128, sync_retry_delay: u64) {
+        self.attempt += 1;
+        let backoff = 1u128 << self.attempt.min(MAX_BACKOFF_EXPONENT);
+        self.deadline = now + sync_retry_delay as u128 * backoff;
+    }
+}
+
+// Stop waiting for a block, and stop fetching the payloads no other waiting block needs.
+fn evict(
+    pending: &mut HashMap<Digest, Waiter>,
+    requests: &mut HashMap<Digest, Fetch>,
+    digest: &Digest,
+) {
+    if let Some(waiter) = pending.remove(digest) {
+        for x in &waiter.missing {
+            if !pending.values().any(|other| other.missing.contains(x)) {
+                requests.remove(x);
+            }
+        }
+    }
+}
+
 impl Synchronizer {
     pub fn new(
         consensus_channel: Sender<ConsensusMessage>,
@@ -37,25 +114,30 @@ impl Synchronizer {
         name: PublicKey,
         committee: Committee,
         network_channel: Sender<NetMessage>,
-        sync_retry_delay: u64,
+        parameters: &Parameters,
     ) -> Self {
         let (tx_inner, mut rx_inner): (_, Receiver<SynchronizerMessage>) = channel(10000);
+        let sync_retry_delay = parameters.sync_retry_delay;
+        let max_pending_per_author = parameters.max_pending_syncs_per_author;
+        let max_pending_per_epoch = parameters.max_pending_syncs_per_epoch;
+        let max_requests_per_author = parameters.max_sync_requests_per_author;
+        let epoch_horizon = parameters.sync_epoch_horizon as EpochNumber;
 
-        let store_copy = store.clone();
+        let mut store_copy = store.clone();
         tokio::spawn(async move {
             let mut waiting = FuturesUnordered::new();
-            let mut pending = HashMap::new();
-            let mut requests = HashMap::new();
+            let mut pending: HashMap<Digest, Waiter> = HashMap::new();
+            let mut requests: HashMap<Digest, Fetch> = HashMap::new();
+            let mut sequence: u64 = 0;
+            let mut cleaned: Option<EpochNumber> = None;
+            let (mut rejected, mut evicted, mut reported) = (0u64, 0u64, (0u64, 0u64));
 
-            let timer = sleep(Duration::from_millis(5000));
+            let timer = sleep(Duration::from_millis(TIMER_RESOLUTION));
             tokio::pin!(timer);
             loop {
                 tokio::select! {
                     Some(message) = rx_inner.recv() => match message {
-                        SynchronizerMessage::Sync(mut missing, block) => {
-                            // TODO [issue #7]: A bad node may make us run out of memory by sending many blocks
-                            // with different round numbers or different payloads.
-
+                        SynchronizerMessage::Sync(mut missing, block, deliver) => {
                             let block_digest = block.digest();
                             let author = block.author;
                             let epoch = block.epoch;
@@ -63,25 +145,108 @@ impl Synchronizer {
                                 continue;
                             }
 
-                            let wait_for = missing.iter().cloned().map(|x| (x, store_copy.clone())).collect();
-                            let (tx_cancel, rx_cancel) = channel(1);
-                            pending.insert(block_digest, (epoch, tx_cancel));
-                            let fut = Self::waiter(wait_for, block, rx_cancel);
-                            waiting.push(fut);
+                            // Blocks of epochs we already cleaned up are useless, and blocks of
+                            // epochs too far ahead could only be used to fill our memory.
+                            if cleaned.map_or(false, |x| epoch <= x || epoch > x.saturating_add(epoch_horizon)) {
+                                rejected += 1;
+                                continue;
+                            }
 
-                            let missing: Vec<_> = missing
-                                .drain()
-                                .filter(|x| !requests.contains_key(x))
-                                .collect();
-                            if !missing.is_empty() {
-                                let now = SystemTime::now()
-                                    .duration_since(UNIX_EPOCH)
-                                    .expect("Failed to measure time")
-                                    .as_millis();
-                                for x in &missing {
-                                    requests.insert(x.clone(), (epoch, now));
+                            // Bound the number of payloads we fetch on behalf of each author.
+                            let new = missing.iter().filter(|x| !requests.contains_key(*x)).count();
+                            let charged = requests.values().filter(|x| x.author == author).count();
+                            if max_requests_per_author > 0 && charged + new > max_requests_per_author {
+                                rejected += 1;
+                                continue;
+                            }
+
+                            // Only blocks waiting for their payload are delivered back to consensus.
+                            if deliver {
+                                // Bound the number of blocks waiting for their payload per author, by
+                                // evicting its oldest waiters.
+                                let of_author = |x: &Waiter| x.author == author;
+                                while max_pending_per_author > 0
+                                    && pending.values().filter(|x| of_author(x)).count() >= max_pending_per_author
+                                {
+                                    let oldest = pending
+                                        .iter()
+                                        .filter(|(_, x)| of_author(x))
+                                        .min_by_key(|(_, x)| x.sequence)
+                                        .map(|(digest, _)| digest.clone());
+                                    if let Some(digest) = oldest {
+                                        evict(&mut pending, &mut requests, &digest);
+                                        evicted += 1;
+                                    }
+                                }
+
+                                // Bound them per epoch as well, by evicting the oldest waiter of the
+                                // author with the most waiters in the epoch (counting this block), so
+                                // that a flooding author cannot evict the blocks of the others.
+                                let of_epoch = |x: &Waiter| x.epoch == epoch;
+                                while max_pending_per_epoch > 0
+                                    && pending.values().filter(|x| of_epoch(x)).count() >= max_pending_per_epoch
+                                {
+                                    let mut counts: HashMap<PublicKey, usize> = HashMap::new();
+                                    *counts.entry(author).or_insert(0) += 1;
+                                    for x in pending.values().filter(|x| of_epoch(x)) {
+                                        *counts.entry(x.author).or_insert(0) += 1;
+                                    }
+                                    let oldest = pending
+                                        .iter()
+                                        .filter(|(_, x)| of_epoch(x))
+                                        .min_by_key(|(_, x)| (std::cmp::Reverse(counts[&x.author]), x.sequence))
+                                        .map(|(digest, _)| digest.clone());
+                                    if let Some(digest) = oldest {
+                                        evict(&mut pending, &mut requests, &digest);
+                                        evicted += 1;
+                                    }
                                 }
 
+                                let wait_for = missing.iter().cloned().map(|x| (x, store_copy.clone())).collect();
+                                let (tx_cancel, rx_cancel) = channel(1);
+                                sequence += 1;
+                                let waiter = Waiter {
+                                    epoch,
+                                    author,
+                                    sequence,
+                                    missing: missing.iter().cloned().collect(),
+                                    cancel: tx_cancel,
+                                };
+                                pending.insert(block_digest, waiter);
+                                let fut = Self::waiter(wait_for, block, rx_cancel);
+                                waiting.push(fut);
+                            }
+
+                            // Only request the payloads we are not already fetching; the author of
+                            // this block is another peer to ask for the others.
+                            let now = SystemTime::now()
+                                .duration_since(UNIX_EPOCH)
+                                .expect("Failed to measure time")
+                                .as_millis();
+                            let mut new = Vec::new();
+                            for x in missing.drain() {
+                                match requests.get_mut(&x) {
+                                    Some(Fetch { holders, .. }) => {
+                                        if !holders.contains(&author) {
+                                            holders.push(author);
+                                        }
+                                    },
+                                    None => {
+                                        let request = Fetch {
+                                            epoch,
+                                            author,
+                                            holders: vec![author],
+                                            asked: vec![author],
+                                            attempt: 0,
+                                            deadline: now + sync_retry_delay as u128,
+                                        };
+                                        requests.insert(x.clone(), request);
+                                        new.push(x);
+                                    }
+                                }
+                            }
+                            let missing = new;
+                            if !missing.is_empty() {
                                 let message = MempoolMessage::PayloadRequest(missing.clone(), name);
                                 Self::transmit(
                                     &message,
@@ -94,14 +259,28 @@ impl Synchronizer {
                                 .expect("Failed to send payload sync request");
                             }
                         },
-                        SynchronizerMessage::Clean(mut epoch) => {
-                            for (e, handler) in pending.values() {
-                                if e <= &epoch {
-                                    let _ = handler.send(()).await;
+                        SynchronizerMessage::Holders(digests, holder) => {
+                            // Ask these peers before the rest of the committee when retrying.
+                            if holder == name {
+                                continue;
+                            }
+                            for x in &digests {
+                                if let Some(Fetch { holders, .. }) = requests.get_mut(x) {
+                                    if !holders.contains(&holder) {
+                                        holders.push(holder);
+                                    }
+                                }
+                            }
+                        },
+                        SynchronizerMessage::Clean(epoch) => {
+                            cleaned = cleaned.max(Some(epoch));
+                            for waiter in pending.values() {
+                                if waiter.epoch <= epoch {
+                                    let _ = waiter.cancel.send(()).await;
                                 }
                             }
-                            pending.retain(|_, (r, _)| r > &mut epoch);
-                            requests.retain(|_, (r, _)| r > &mut epoch);
+                            pending.retain(|_, waiter| waiter.epoch > epoch);
+                            requests.retain(|_, request| request.epoch > epoch);
                         }
                     },
                     Some(result) = waiting.next() => {
@@ -126,25 +305,49 @@ impl Synchronizer {
                             .duration_since(UNIX_EPOCH)
                             .expect("Failed to measure time")
                             .as_millis();
-                        let retransmit: Vec<_> = requests
+                        let expired: Vec<_> = requests
                             .iter()
-                            .filter(|(_, (_, timestamp))| timestamp + (sync_retry_delay as u128) < now)
-                            .map(|(digest, _)| digest)
-                            .cloned()
+                            .filter(|(_, request)| request.deadline < now)
+                            .map(|(digest, _)| digest.clone())
                             .collect();
-                        if !retransmit.is_empty() {
-                            let message = MempoolMessage::PayloadRequest(retransmit, name);
+                        let mut retransmit: HashMap<PublicKey, Vec<Digest>> = HashMap::new();
+                        for digest in expired {
+                            // Prefetched payloads have no waiter to clear their request.
+                            if let Ok(Some(_)) = store_copy.read(digest.to_vec()).await {
+                                requests.remove(&digest);
+                                continue;
+                            }
+                            if let Some(request) = requests.get_mut(&digest) {
+                                request.retry(now, sync_retry_delay);
+                                retransmit
+                                    .entry(request.peer(&name, &committee))
+                                    .or_insert_with(Vec::new)
+                                    .push(digest);
+                            }
+                        }
+                        for (peer, digests) in retransmit {
+                            let message = MempoolMessage::PayloadRequest(digests, name);
                             Self::transmit(
                                 &message,
                                 &name,
-                                None,
+                                Some(&peer),
                                 &committee,
                                 &network_channel
                             )
                             .await
                             .expect("Failed to send payload sync request");
                         }
-                        timer.as_mut().reset(Instant::now() + Duration::from_millis(5000));
+
+                        if (rejected, evicted) != reported {
+                            info!(
+                                "Payload sync: {} blocks pending, {} stale requests rejected, {} waiters evicted",
+                                pending.len(),
+                                rejected,
+                                evicted
+                            );
+                            reported = (rejected, evicted);
+                        }
+                        timer.as_mut().reset(Instant::now() + Duration::from_millis(TIMER_RESOLUTION));
                     },
                     else => break,
                 }
@@ -207,13 +410,38 @@ impl Synchronizer {
         if missing.is_empty() {
             return Ok(true);
         }
-        let message = SynchronizerMessage::Sync(missing, block);
+        let message = SynchronizerMessage::Sync(missing, block, true);
         if let Err(e) = self.inner_channel.send(message).await {
             panic!("Failed to send message to synchronizer core: {}", e);
         }
         Ok(false)
     }
 
+    // Fetch the missing payload of a block without delivering the block back to consensus.
+    pub async fn prefetch(&mut self, block: Block) -> MempoolResult<()> {
+        let mut missing = HashSet::new();
+        for digest in &block.payload {
+            if self.store.read(digest.to_vec()).await?.is_none() {
+                missing.insert(digest.clone());
+            }
+        }
+        if !missing.is_empty() {
+            let message = SynchronizerMessage::Sync(missing, block, false);
+            if let Err(e) = self.inner_channel.send(message).await {
+                panic!("Failed to send message to synchronizer core: {}", e);
+            }
+        }
+        Ok(())
+    }
+
+    // Record that a peer should hold some payloads, so that we also ask it for them.
+    pub async fn holders(&mut self, digests: Vec<Digest>, holder: PublicKey) {
+        let message = SynchronizerMessage::Holders(digests, holder);
+        if let Err(e) = self.inner_channel.send(message).await {
+            panic!("Failed to send message to synchronizer core: {}", e);
+        }
+    }
+
     pub async fn cleanup(&mut self, epoch: EpochNumber) {
         let message = SynchronizerMessage::Clean(epoch);
         debug!("cleanup epoch {}", epoch);
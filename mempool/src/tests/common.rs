@@ -2,7 +2,7 @@ use crate::config::Committee;
 use crate::messages::Payload;
 use consensus::{Block, Proof};
 use crypto::Hash as _;
-use crypto::{generate_keypair, PublicKey, SecretKey, Signature};
+use crypto::{generate_keypair, Digest, PublicKey, SecretKey, Signature};
 use rand::rngs::StdRng;
 use rand::SeedableRng as _;
 
@@ -61,8 +61,11 @@ pub fn block() -> Block {
     let (author, secret) = keys().pop().unwrap();
     let block = Block {
         author,
+        parent: Digest::default(),
         view: 0,
+        timestamp: 0,
         payload: Vec::new(),
+        certificates: Vec::new(),
         signature: Signature::default(),
         epoch: 0,
         proof: Proof::Pi(Vec::new()),
@@ -0,0 +1,98 @@
+use super::*;
+use crate::common::payload;
+use crypto::Hash as _;
+
+fn controller(target_latency: u64) -> Controller {
+    Controller::new(target_latency, 1_000, 160, BatchingMetrics::default())
+}
+
+#[test]
+fn disabled() {
+    let mut controller = controller(0);
+    let now = Instant::now();
+    controller.queued(&payload().digest(), now);
+    controller.committed(&[payload().digest()], now);
+    assert_eq!(controller.adjust(100), None);
+    assert_eq!(controller.payload_size(), 1_000);
+    assert_eq!(controller.block_size(320), 10);
+}
+
+#[test]
+fn shrink_payloads_over_target() {
+    let metrics = BatchingMetrics::default();
+    let mut controller = Controller::new(1, 1_000, 160, metrics.clone());
+    let digest = payload().digest();
+    let start = Instant::now();
+    controller.queued(&digest, start);
+    controller.committed(&[digest], start + Duration::from_millis(10));
+    assert_eq!(controller.adjust(0), Some((750, 120)));
+    assert_eq!(controller.payload_size(), 750);
+
+    // The decision is visible through the shared metrics.
+    assert_eq!(metrics.latency(), 10);
+    assert_eq!(metrics.payload_size(), 750);
+    assert_eq!(metrics.block_delay(), 120);
+    assert_eq!(metrics.decreases(), 1);
+    assert_eq!(metrics.increases(), 0);
+}
+
+#[test]
+fn adapt_block_delay() {
+    let mut controller = controller(5);
+    let start = Instant::now();
+    let slow = payload().digest();
+    controller.queued(&slow, start);
+    controller.committed(&[slow], start + Duration::from_millis(100));
+
+    // Payloads pile up in the queue: sealing them faster would not help.
+    assert_eq!(controller.adjust(8), Some((750, 160)));
+
+    // The queue is drained: seal payloads more often, down to a floor.
+    assert_eq!(controller.adjust(0), Some((562, 120)));
+    assert_eq!(controller.adjust(0), Some((421, 90)));
+    for _ in 0..10 {
+        controller.adjust(0);
+    }
+    assert_eq!(controller.adjust(0), None);
+    assert_eq!(controller.payload_size(), 62);
+
+    // Slow down again once the latency is back under target.
+    let fast = payload().digest();
+    let now = start + Duration::from_millis(100);
+    for _ in 0..20 {
+        controller.queued(&fast, now);
+        controller.committed(&[fast.clone()], now);
+    }
+    assert_eq!(controller.adjust(0), Some((124, 20)));
+}
+
+#[test]
+fn adapt_block_size() {
+    let mut controller = controller(10_000);
+    let digest = payload().digest();
+    let now = Instant::now();
+    controller.queued(&digest, now);
+    controller.committed(&[digest], now);
+
+    // Latency is under target and payloads are already as large as possible.
+    assert_eq!(controller.adjust(8), None);
+
+    // Blocks start as large as consensus accepts.
+    assert_eq!(controller.block_size(320), 10);
+
+    // Shrink blocks while the queue is nearly empty.
+    controller.adjust(0);
+    assert_eq!(controller.block_size(320), 5);
+    controller.adjust(0);
+    controller.adjust(0);
+    assert_eq!(controller.block_size(320), 1);
+
+    // And grow them back with the queue.
+    controller.adjust(8);
+    controller.adjust(8);
+    controller.adjust(8);
+    assert_eq!(controller.block_size(320), 8);
+
+    // The block size is still bounded by consensus.
+    assert_eq!(controller.block_size(64), 2);
+}
@@ -1,6 +1,9 @@
 use super::*;
 use crate::common::{committee, keys, payload};
+use crate::controller::BatchingMetrics;
 use crate::messages::Transaction;
+use crate::quota::QuotaUsage;
+use crate::validator::AcceptAll;
 use crypto::SignatureService;
 use threshold_crypto::SecretKeyShare;
 use std::fs;
@@ -9,8 +12,47 @@ use tokio::sync::mpsc::channel;
 use tokio::sync::oneshot;
 use tokio::time::sleep;
 
+fn parameters() -> Parameters {
+    Parameters {
+        queue_capacity: 1,
+        sync_retry_delay: 10_000,
+        max_payload_size: 1,
+        min_block_delay: 0,
+        ..Parameters::default()
+    }
+}
+
 async fn core(
     store_path: &str,
+    parameters: Parameters,
+) -> (
+    Receiver<NetMessage>,
+    Sender<MempoolMessage>,
+    Sender<ConsensusMempoolMessage>,
+    Sender<Transaction>,
+) {
+    core_with_validator(store_path, parameters, Arc::new(AcceptAll)).await
+}
+
+async fn core_with_validator(
+    store_path: &str,
+    parameters: Parameters,
+    validator: Arc<dyn TransactionValidator>,
+) -> (
+    Receiver<NetMessage>,
+    Sender<MempoolMessage>,
+    Sender<ConsensusMempoolMessage>,
+    Sender<Transaction>,
+) {
+    let _ = fs::remove_dir_all(store_path);
+    let store = Store::new(store_path).unwrap();
+    core_with_store(store, parameters, validator)
+}
+
+fn core_with_store(
+    store: Store,
+    parameters: Parameters,
+    validator: Arc<dyn TransactionValidator>,
 ) -> (
     Receiver<NetMessage>,
     Sender<MempoolMessage>,
@@ -24,43 +66,48 @@ async fn core(
     let (tx_client, rx_client) = channel(1);
 
     let (name, secret) = keys().pop().unwrap();
-    let parameters = Parameters {
-        queue_capacity: 1,
-        sync_retry_delay: 10_000,
-        max_payload_size: 1,
-        min_block_delay: 0,
-    };
     let signature_service = SignatureService::new(secret, SecretKeyShare::default());
-    let _ = fs::remove_dir_all(store_path);
-    let store = Store::new(store_path).unwrap();
     let synchronizer = Synchronizer::new(
         tx_consensus,
         store.clone(),
         name,
         committee(),
         tx_network.clone(),
-        parameters.sync_retry_delay,
+        &parameters,
     );
     let payload_maker = PayloadMaker::new(
         name,
-        signature_service,
+        signature_service.clone(),
         parameters.max_payload_size,
         parameters.min_block_delay,
+        parameters.lane_weights.len().max(1),
+        validator.clone(),
         rx_client,
         tx_core.clone(),
     );
+    let shared = Shared {
+        signature_service,
+        synchronizer,
+        validator,
+        dedup: Arc::new(Mutex::new(DedupIndex::new(parameters.dedup_window))),
+        quota_usage: Arc::new(Mutex::new(QuotaUsage::default())),
+        metrics: BatchingMetrics::default(),
+        execution: ExecutionState::default(),
+    };
     let mut core = Core::new(
         name,
         committee(),
         parameters,
         store,
-        synchronizer,
         payload_maker,
         /* core_channel */ rx_core,
         /* consensus_channel */ rx_consensus_mempool,
         /* network_channel */ tx_network,
+        shared,
+        WorkerState::default(),
     );
     tokio::spawn(async move {
+        core.recover().await.unwrap();
         core.run().await;
     });
 
@@ -71,7 +118,8 @@ async fn core(
 async fn handle_transaction() {
     // Run the core.
     let path = ".db_test_handle_transaction";
-    let (mut rx_network, _tx_core, _tx_consensus, tx_client) = core(path).await;
+    let (mut rx_network, _tx_core, _tx_consensus, tx_client) =
+        core(path, parameters()).await;
 
     // Ensure the core transmits the payload to the network.
     tx_client.send(vec![1u8]).await.unwrap();
@@ -83,7 +131,8 @@ async fn handle_transaction() {
 async fn handle_request() {
     // Run the core.
     let path = ".db_test_handle_request";
-    let (mut rx_network, tx_core, _tx_consensus, _tx_client) = core(path).await;
+    let (mut rx_network, tx_core, _tx_consensus, _tx_client) =
+        core(path, parameters()).await;
 
     // Send a payload to the core.
     let message = MempoolMessage::Payload(payload());
@@ -104,7 +153,8 @@ async fn handle_request() {
 async fn get_payload() {
     // Run the core.
     let path = ".db_test_get_payload";
-    let (_rx_network, _tx_core, tx_consensus, tx_client) = core(path).await;
+    let (_rx_network, _tx_core, tx_consensus, tx_client) =
+        core(path, parameters()).await;
 
     // Send enough transactions to generate a payload.
     tx_client.send(vec![1u8]).await.unwrap();
@@ -112,8 +162,298 @@ async fn get_payload() {
 
     // Get the next payload.
     let (sender, receiver) = oneshot::channel();
-    let message = ConsensusMempoolMessage::Get(64, sender);
+    let message = ConsensusMempoolMessage::Get(64, 1, sender);
     tx_consensus.send(message).await.unwrap();
     let result = receiver.await.unwrap();
     assert_eq!(result, vec![payload().digest()]);
 }
+
+#[tokio::test]
+async fn persist_proposed_payload() {
+    // Run the core with payloads large enough not to be sealed before consensus asks.
+    let path = ".db_test_persist_proposed_payload";
+    let _ = fs::remove_dir_all(path);
+    let mut store = Store::new(path).unwrap();
+    let parameters = Parameters {
+        max_payload_size: 1_000,
+        ..parameters()
+    };
+    let (_rx_network, _tx_core, tx_consensus, tx_client) =
+        core_with_store(store.clone(), parameters, Arc::new(AcceptAll));
+    tx_client.send(vec![1u8]).await.unwrap();
+    sleep(Duration::from_millis(50)).await;
+
+    // Get a payload sealed on the spot.
+    let (sender, receiver) = oneshot::channel();
+    let message = ConsensusMempoolMessage::Get(64, 1, sender);
+    tx_consensus.send(message).await.unwrap();
+    let result = receiver.await.unwrap();
+    assert_eq!(result.len(), 1);
+
+    // Ensure it is queued again after a restart.
+    assert!(store.read(queue_key(&result[0])).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn repropose_uncommitted() {
+    // Run the core.
+    let path = ".db_test_repropose_uncommitted";
+    let (_rx_network, _tx_core, tx_consensus, tx_client) =
+        core(path, parameters()).await;
+
+    // Send enough transactions to generate a payload.
+    tx_client.send(vec![1u8]).await.unwrap();
+    tx_client.send(vec![1u8]).await.unwrap();
+
+    // Propose the payload in epoch 1.
+    let (sender, receiver) = oneshot::channel();
+    let message = ConsensusMempoolMessage::Get(64, 1, sender);
+    tx_consensus.send(message).await.unwrap();
+    assert_eq!(receiver.await.unwrap(), vec![payload().digest()]);
+
+    // The block of another node is committed in epoch 1.
+    let message = ConsensusMempoolMessage::Cleanup(Vec::new(), 1, Digest::default());
+    tx_consensus.send(message).await.unwrap();
+
+    // Ensure the payload is proposed again in epoch 2.
+    let (sender, receiver) = oneshot::channel();
+    let message = ConsensusMempoolMessage::Get(64, 2, sender);
+    tx_consensus.send(message).await.unwrap();
+    assert_eq!(receiver.await.unwrap(), vec![payload().digest()]);
+}
+
+#[tokio::test]
+async fn disperse_fragments() {
+    // Run the core.
+    let path = ".db_test_disperse_fragments";
+    let (mut rx_network, _tx_core, _tx_consensus, tx_client) =
+        core(path, Parameters { dissemination: Dissemination::Erasure, ..parameters() }).await;
+
+    // Ensure the core sends one fragment of the payload to every other node.
+    tx_client.send(vec![1u8]).await.unwrap();
+    tx_client.send(vec![1u8]).await.unwrap();
+    let (name, _) = keys().pop().unwrap();
+    for _ in 0..committee().size() - 1 {
+        match rx_network.recv().await {
+            Some(NetMessage(bytes, recipients)) => {
+                match bincode::deserialize(&bytes).unwrap() {
+                    MempoolMessage::Fragment(fragment) => {
+                        assert_eq!(fragment.digest, payload().digest());
+                        assert!(fragment.verify(&committee()).is_ok());
+                        let member = committee().members()[fragment.index];
+                        assert!(member != name);
+                        assert_eq!(recipients, vec![committee().mempool_address(&member).unwrap()]);
+                    }
+                    _ => assert!(false),
+                }
+            }
+            _ => assert!(false),
+        }
+    }
+}
+
+#[tokio::test]
+async fn rebuild_from_fragments() {
+    // Run the core.
+    let path = ".db_test_rebuild_from_fragments";
+    let parameters = Parameters {
+        dissemination: Dissemination::Erasure,
+        ..parameters()
+    };
+    let (mut rx_network, tx_core, _tx_consensus, _tx_client) = core(path, parameters).await;
+
+    // Another node makes a payload and erasure-codes it.
+    let (author, secret) = keys().remove(0);
+    let payload = Payload {
+        transactions: vec![vec![2u8]],
+        author,
+        signature: Signature::default(),
+    };
+    let digest = payload.digest();
+    let payload = Payload {
+        signature: Signature::new(&digest, &secret),
+        ..payload
+    };
+    let threshold = committee().reconstruction_threshold();
+    let codec = Codec::new(threshold, committee().size()).unwrap();
+    let shards = codec.encode(&bincode::serialize(&payload).unwrap());
+    let leaves: Vec<_> = shards.iter().map(|x| leaf(x)).collect();
+    let root = merkle_root(&leaves);
+    let signature = Signature::new(&Fragment::commitment(&digest, &root), &secret);
+
+    // Send the core just enough fragments, none of them its own.
+    let (name, _) = keys().pop().unwrap();
+    let own = committee().index(&name).unwrap();
+    for index in (0..shards.len()).filter(|x| *x != own).take(threshold) {
+        let fragment = Fragment {
+            digest: digest.clone(),
+            root: root.clone(),
+            index,
+            data: shards[index].clone(),
+            proof: merkle_proof(&leaves, index),
+            author,
+            signature: signature.clone(),
+        };
+        let message = MempoolMessage::Fragment(fragment);
+        tx_core.send(message).await.unwrap();
+    }
+
+    // Ensure the core rebuilt the payload.
+    let (requestor, _) = keys().remove(1);
+    let message = MempoolMessage::PayloadRequest(vec![digest.clone()], requestor);
+    tx_core.send(message).await.unwrap();
+    match rx_network.recv().await {
+        Some(NetMessage(bytes, _)) => match bincode::deserialize(&bytes).unwrap() {
+            MempoolMessage::Payload(x) => assert_eq!(x.digest(), digest),
+            _ => assert!(false),
+        },
+        _ => assert!(false),
+    }
+}
+
+#[tokio::test]
+async fn certify_payload() {
+    // Run the core.
+    let path = ".db_test_certify_payload";
+    let parameters = Parameters {
+        availability_certificates: true,
+        ..parameters()
+    };
+    let (mut rx_network, tx_core, tx_consensus, tx_client) = core(path, parameters).await;
+
+    // Send enough transactions to generate a payload.
+    tx_client.send(vec![1u8]).await.unwrap();
+    tx_client.send(vec![1u8]).await.unwrap();
+    assert!(rx_network.recv().await.is_some());
+
+    // Ensure the payload is not proposed before it is certified.
+    let (sender, receiver) = oneshot::channel();
+    let message = ConsensusMempoolMessage::Get(64, 1, sender);
+    tx_consensus.send(message).await.unwrap();
+    assert!(receiver.await.unwrap().is_empty());
+
+    // Another node acknowledges it stored the payload.
+    let (voter, secret) = keys().remove(0);
+    let digest = payload().digest();
+    let signature = Signature::new(&Certificate::vote_digest(&digest), &secret);
+    let message = MempoolMessage::PayloadAck(digest.clone(), voter, signature);
+    tx_core.send(message).await.unwrap();
+    sleep(Duration::from_millis(50)).await;
+
+    // Ensure the payload is now proposed.
+    let (sender, receiver) = oneshot::channel();
+    let message = ConsensusMempoolMessage::Get(64, 1, sender);
+    tx_consensus.send(message).await.unwrap();
+    assert_eq!(receiver.await.unwrap(), vec![digest]);
+}
+
+#[tokio::test]
+async fn announce_payloads() {
+    // Run the core.
+    let path = ".db_test_announce_payloads";
+    let parameters = Parameters {
+        dissemination: Dissemination::Pull,
+        ..parameters()
+    };
+    let (mut rx_network, _tx_core, _tx_consensus, tx_client) = core(path, parameters).await;
+
+    // Ensure the core announces the digest of the payload instead of pushing it.
+    tx_client.send(vec![1u8]).await.unwrap();
+    tx_client.send(vec![1u8]).await.unwrap();
+    let (name, _) = keys().pop().unwrap();
+    match rx_network.recv().await {
+        Some(NetMessage(bytes, _)) => match bincode::deserialize(&bytes).unwrap() {
+            MempoolMessage::Announce(digests, author) => {
+                assert_eq!(digests, vec![payload().digest()]);
+                assert_eq!(author, name);
+            }
+            _ => assert!(false),
+        },
+        _ => assert!(false),
+    }
+}
+
+#[tokio::test]
+async fn pull_announced_payloads() {
+    // Run the core.
+    let path = ".db_test_pull_announced_payloads";
+    let (mut rx_network, tx_core, _tx_consensus, _tx_client) = core(path, parameters()).await;
+
+    // Another node announces a payload we do not have.
+    let (author, _) = keys().remove(0);
+    let digest = payload().digest();
+    let message = MempoolMessage::Announce(vec![digest.clone()], author);
+    tx_core.send(message).await.unwrap();
+
+    // Ensure we pull it from that node.
+    match rx_network.recv().await {
+        Some(NetMessage(bytes, recipients)) => {
+            match bincode::deserialize(&bytes).unwrap() {
+                MempoolMessage::PayloadRequest(digests, _) => assert_eq!(digests, vec![digest]),
+                _ => assert!(false),
+            }
+            assert_eq!(recipients, vec![committee().mempool_address(&author).unwrap()]);
+        }
+        _ => assert!(false),
+    }
+}
+
+struct RejectAll;
+
+impl TransactionValidator for RejectAll {
+    fn validate(&self, _transaction: &Transaction) -> Result<(), String> {
+        Err("Rejected".to_string())
+    }
+}
+
+#[tokio::test]
+async fn reject_invalid_payload() {
+    // Run the core.
+    let path = ".db_test_reject_invalid_payload";
+    let (mut rx_network, tx_core, _tx_consensus, _tx_client) =
+        core_with_validator(path, parameters(), Arc::new(RejectAll)).await;
+
+    // Send a payload with invalid transactions to the core.
+    let message = MempoolMessage::Payload(payload());
+    tx_core.send(message).await.unwrap();
+    sleep(Duration::from_millis(50)).await;
+
+    // Ensure the payload was not stored: we still pull it when it is announced.
+    let (author, _) = keys().remove(0);
+    let message = MempoolMessage::Announce(vec![payload().digest()], author);
+    tx_core.send(message).await.unwrap();
+    match rx_network.recv().await {
+        Some(NetMessage(bytes, _)) => match bincode::deserialize(&bytes).unwrap() {
+            MempoolMessage::PayloadRequest(digests, _) => {
+                assert_eq!(digests, vec![payload().digest()])
+            }
+            _ => assert!(false),
+        },
+        _ => assert!(false),
+    }
+}
+
+#[tokio::test]
+async fn recover_queue() {
+    // Persist a queued payload, as before a restart.
+    let path = ".db_test_recover_queue";
+    let _ = fs::remove_dir_all(path);
+    let mut store = Store::new(path).unwrap();
+    let (name, _) = keys().pop().unwrap();
+    let digest = payload().digest();
+    let value = bincode::serialize(&payload()).unwrap();
+    store.write(digest.to_vec(), value).await;
+    let value = bincode::serialize(&name).unwrap();
+    store.write(queue_key(&digest), value).await;
+
+    // Run the core.
+    let (_rx_network, _tx_core, tx_consensus, _tx_client) =
+        core_with_store(store, parameters(), Arc::new(AcceptAll));
+
+    // Ensure the payload is proposed again.
+    let (sender, receiver) = oneshot::channel();
+    let message = ConsensusMempoolMessage::Get(64, 1, sender);
+    tx_consensus.send(message).await.unwrap();
+    let result = receiver.await.unwrap();
+    assert_eq!(result, vec![digest]);
+}
@@ -0,0 +1,65 @@
+use super::*;
+
+#[test]
+fn commit_filter() {
+    let mut filter = CommitFilter::new(2);
+
+    // Duplicates within an epoch are dropped.
+    let committed = filter.filter(1, vec![vec![1u8], vec![2u8], vec![1u8]]);
+    assert_eq!(committed, vec![vec![1u8], vec![2u8]]);
+
+    // And so are the transactions committed in the window.
+    let committed = filter.filter(2, vec![vec![2u8], vec![3u8]]);
+    assert_eq!(committed, vec![vec![3u8]]);
+
+    // Transactions out of the window may be committed again.
+    let committed = filter.filter(3, vec![vec![1u8], vec![3u8]]);
+    assert_eq!(committed, vec![vec![1u8]]);
+}
+
+#[test]
+fn commit_filter_disabled() {
+    let mut filter = CommitFilter::new(0);
+    let committed = filter.filter(1, vec![vec![1u8], vec![1u8]]);
+    assert_eq!(committed, vec![vec![1u8], vec![1u8]]);
+}
+
+#[test]
+fn reject_pending_and_committed() {
+    let mut index = DedupIndex::new(10);
+    let transaction = vec![1u8];
+    let digest = transaction_digest(&transaction);
+    let payload = Digest([0u8; 32]);
+
+    // A client transaction is only accepted once.
+    assert!(index.submit(digest.clone()));
+    assert!(!index.submit(digest.clone()));
+
+    // It stays known once packed into a payload and committed.
+    index.add_payload(payload.clone(), &[transaction.clone()]);
+    assert!(index.contains(&digest));
+    index.commit(1, &[payload]);
+    assert!(!index.submit(digest.clone()));
+
+    // Until it leaves the window.
+    index.commit(11, &[]);
+    assert!(index.submit(digest));
+}
+
+#[test]
+fn release_dropped_transactions() {
+    let mut index = DedupIndex::new(10);
+    let transaction = vec![1u8];
+    let digest = transaction_digest(&transaction);
+    let payload = Digest([0u8; 32]);
+
+    // Transactions that did not make it into a payload may be submitted again.
+    assert!(index.submit(digest.clone()));
+    index.drop_transactions(&[transaction.clone()]);
+    assert!(index.submit(digest.clone()));
+
+    // And so may those of payloads that will never be committed.
+    index.add_payload(payload.clone(), &[transaction]);
+    index.remove_payload(&payload);
+    assert!(index.submit(digest));
+}
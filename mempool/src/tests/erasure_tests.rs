@@ -0,0 +1,49 @@
+use super::*;
+
+#[test]
+fn encode_decode() {
+    let codec = Codec::new(2, 4).unwrap();
+    let data: Vec<u8> = (0..=255).collect();
+    let fragments = codec.encode(&data);
+    assert_eq!(fragments.len(), 4);
+
+    // Any two fragments recover the data.
+    for i in 0..4 {
+        for j in 0..4 {
+            if i != j {
+                let subset = vec![(i, fragments[i].clone()), (j, fragments[j].clone())];
+                assert_eq!(codec.decode(&subset), Some(data.clone()));
+            }
+        }
+    }
+}
+
+#[test]
+fn decode_too_few_fragments() {
+    let codec = Codec::new(3, 7).unwrap();
+    let fragments = codec.encode(b"hello world");
+    let subset = vec![(5, fragments[5].clone()), (5, fragments[5].clone())];
+    assert_eq!(codec.decode(&subset), None);
+
+    let subset: Vec<_> = (4..7).map(|i| (i, fragments[i].clone())).collect();
+    assert_eq!(codec.decode(&subset), Some(b"hello world".to_vec()));
+}
+
+#[test]
+fn reject_invalid_parameters() {
+    assert!(Codec::new(0, 4).is_err());
+    assert!(Codec::new(5, 4).is_err());
+    assert!(Codec::new(85, 256).is_err());
+    assert!(Codec::new(85, 255).is_ok());
+}
+
+#[test]
+fn merkle_proofs() {
+    let leaves: Vec<_> = (0..5u8).map(|i| leaf(&[i])).collect();
+    let root = merkle_root(&leaves);
+    for (i, x) in leaves.iter().enumerate() {
+        let proof = merkle_proof(&leaves, i);
+        assert!(verify_merkle_proof(&root, x, i, &proof));
+        assert!(!verify_merkle_proof(&root, x, (i + 1) % 5, &proof));
+    }
+}
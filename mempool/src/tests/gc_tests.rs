@@ -0,0 +1,60 @@
+use super::*;
+use crate::common::payload;
+use crypto::Hash as _;
+use std::fs;
+
+#[tokio::test]
+async fn collect_committed_payloads() {
+    let path = ".db_test_collect_committed_payloads";
+    let _ = fs::remove_dir_all(path);
+    let mut store = Store::new(path).unwrap();
+    let progress = ExecutionProgress::default();
+    let mut gc = GarbageCollector::new(store.clone(), 2, progress.clone());
+
+    // Store a payload and index its transactions.
+    let digest = payload().digest();
+    let value = bincode::serialize(&payload()).unwrap();
+    store.write(digest.to_vec(), value).await;
+    let key = transaction_key(&transaction_digest(&vec![1u8]));
+    let value = bincode::serialize(&digest).unwrap();
+    store.write(key.clone(), value).await;
+
+    // The payload is retained for two epochs after its commit.
+    gc.committed(1, vec![digest.clone()]).await;
+    gc.committed(2, Vec::new()).await;
+    gc.flush().await;
+    assert!(store.read(digest.to_vec()).await.unwrap().is_some());
+
+    // And until the node executed it.
+    gc.committed(3, Vec::new()).await;
+    gc.flush().await;
+    assert!(store.read(digest.to_vec()).await.unwrap().is_some());
+
+    progress.advance(3);
+    gc.committed(4, Vec::new()).await;
+    gc.flush().await;
+    assert!(store.read(digest.to_vec()).await.unwrap().is_none());
+    assert!(store.read(key).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn collect_expired_payloads() {
+    let path = ".db_test_collect_expired_payloads";
+    let _ = fs::remove_dir_all(path);
+    let mut store = Store::new(path).unwrap();
+    let mut gc = GarbageCollector::new(store.clone(), 0, ExecutionProgress::default());
+
+    let digest = payload().digest();
+    let value = bincode::serialize(&payload()).unwrap();
+    store.write(digest.to_vec(), value).await;
+
+    // Committed payloads are kept forever, expired ones are deleted at once.
+    gc.committed(1, vec![digest.clone()]).await;
+    gc.committed(100, Vec::new()).await;
+    gc.flush().await;
+    assert!(store.read(digest.to_vec()).await.unwrap().is_some());
+
+    gc.expired(vec![digest.clone()]).await;
+    gc.flush().await;
+    assert!(store.read(digest.to_vec()).await.unwrap().is_none());
+}
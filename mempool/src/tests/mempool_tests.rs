@@ -1,11 +1,15 @@
 use super::*;
 use crate::common::{block, committee, keys, payload};
-use crate::config::Parameters;
+use crate::config::{Parameters, WorkerAddresses};
+use crate::messages::{transaction_digest, Reply};
+use crate::validator::AcceptAll;
 use bytes::Bytes;
 use consensus::{Block, PayloadStatus};
+use crypto::Digest;
 use crypto::Hash as _;
 use futures::future::try_join_all;
 use futures::sink::SinkExt as _;
+use futures::stream::StreamExt as _;
 use std::fs;
 use std::time::Duration;
 use threshold_crypto::SecretKeyShare;
@@ -30,6 +34,7 @@ async fn end_to_end() {
                 sync_retry_delay: 10_000,
                 max_payload_size: 1,
                 min_block_delay: 0,
+                ..Parameters::default()
             };
             let signature_service = SignatureService::new(secret, SecretKeyShare::default());
             let store_path = format!(".db_test_end_to_end_{}", i);
@@ -47,6 +52,9 @@ async fn end_to_end() {
                     signature_service,
                     tx_consensus,
                     rx_consensus_mempool,
+                    Arc::new(AcceptAll),
+                    BatchingMetrics::default(),
+                    ExecutionState::default(),
                 )
                 .unwrap();
                 sleep(Duration::from_millis(100)).await;
@@ -89,3 +97,83 @@ async fn end_to_end() {
     // Ensure all threads terminated correctly.
     assert!(try_join_all(mempool_handles).await.is_ok());
 }
+
+#[tokio::test]
+async fn workers_share_store() {
+    let (name, secret) = keys().pop().unwrap();
+    let mut committee = committee();
+    committee.increment_base_port(5100);
+    let worker = WorkerAddresses {
+        front_address: "127.0.0.1:5110".parse().unwrap(),
+        mempool_address: "127.0.0.1:5111".parse().unwrap(),
+    };
+    committee
+        .authorities
+        .get_mut(&name)
+        .unwrap()
+        .workers
+        .push(worker.clone());
+
+    // Run an authority with two workers.
+    let parameters = Parameters {
+        max_payload_size: 1,
+        min_block_delay: 0,
+        ..Parameters::default()
+    };
+    let signature_service = SignatureService::new(secret, SecretKeyShare::default());
+    let store_path = ".db_test_workers_share_store";
+    let _ = fs::remove_dir_all(store_path);
+    let store = Store::new(store_path).unwrap();
+    let (tx_consensus, _rx_consensus) = channel(1);
+    let (tx_consensus_mempool, rx_consensus_mempool) = channel(1);
+    Mempool::run(
+        name,
+        committee,
+        parameters,
+        store,
+        signature_service,
+        tx_consensus,
+        rx_consensus_mempool,
+        Arc::new(AcceptAll),
+        BatchingMetrics::default(),
+        ExecutionState::default(),
+    )
+    .unwrap();
+    sleep(Duration::from_millis(50)).await;
+
+    // Submit a transaction to the second worker, and wait for it to be queued.
+    let stream = TcpStream::connect(worker.front_address).await.unwrap();
+    let mut transport = Framed::new(stream, LengthDelimitedCodec::new());
+    transport.send(Bytes::from(vec![1u8])).await.unwrap();
+    let reply = transport.next().await.unwrap().unwrap();
+    let reply: Reply = bincode::deserialize(&reply).unwrap();
+    assert_eq!(reply, Reply::Accepted(transaction_digest(&vec![1u8])));
+
+    // Consensus gets the payload of the second worker.
+    let (sender, receiver) = oneshot::channel();
+    let message = ConsensusMempoolMessage::Get(10, 1, sender);
+    tx_consensus_mempool.send(message).await.unwrap();
+    let digests = receiver.await.unwrap();
+    assert_eq!(digests.len(), 1);
+
+    // The first worker verifies it from the store they share.
+    let block = Block {
+        payload: digests.clone(),
+        ..block()
+    };
+    let (sender, receiver) = oneshot::channel();
+    let message = ConsensusMempoolMessage::Verify(Box::new(block), sender);
+    tx_consensus_mempool.send(message).await.unwrap();
+    match receiver.await {
+        Ok(PayloadStatus::Accept) => (),
+        x => panic!("Unexpected payload status: {:?}", x),
+    }
+
+    // Once committed, the second worker stops proposing it.
+    let message = ConsensusMempoolMessage::Cleanup(digests, 1, Digest::default());
+    tx_consensus_mempool.send(message).await.unwrap();
+    let (sender, receiver) = oneshot::channel();
+    let message = ConsensusMempoolMessage::Get(10, 2, sender);
+    tx_consensus_mempool.send(message).await.unwrap();
+    assert!(receiver.await.unwrap().is_empty());
+}
@@ -0,0 +1,86 @@
+use super::*;
+use crate::common::keys;
+use crate::validator::{AcceptAll, SignedValidator, TransactionValidator};
+use std::sync::Arc;
+
+// Fixture.
+fn transaction(nonce: u64, payload: u8) -> Transaction {
+    let (sender, secret) = keys().pop().unwrap();
+    SignedTransaction::new(sender, nonce, vec![payload], &secret).to_transaction()
+}
+
+#[test]
+fn verify_signed_transactions() {
+    let validator = SignedValidator::new(Arc::new(AcceptAll));
+    assert!(validator.validate(&transaction(1, 1)).is_ok());
+
+    // Opaque transactions are rejected.
+    assert!(validator.validate(&vec![1u8]).is_err());
+
+    // And so are tampered transactions.
+    let mut signed = SignedTransaction::from_transaction(&transaction(1, 1)).unwrap();
+    signed.nonce = 2;
+    assert!(validator.validate(&signed.to_transaction()).is_err());
+
+    // Or transactions with trailing bytes.
+    let mut tampered = transaction(1, 1);
+    tampered.push(0u8);
+    assert!(validator.validate(&tampered).is_err());
+}
+
+#[test]
+fn accept_increasing_nonces() {
+    let (sender, _) = keys().pop().unwrap();
+    let mut filter = NonceFilter::default();
+    assert!(filter.accept(sender, 1));
+    assert!(filter.accept(sender, 3));
+    assert!(!filter.accept(sender, 3));
+    assert!(!filter.accept(sender, 2));
+    assert_eq!(filter.nonce(&sender), Some(3));
+
+    // Senders are independent.
+    let (other, _) = keys().remove(0);
+    assert!(filter.accept(other, 1));
+}
+
+#[test]
+fn filter_committed_transactions() {
+    let mut filter = NonceFilter::default();
+    let committed = filter.filter(vec![
+        transaction(1, 1),
+        transaction(3, 3),
+        transaction(2, 2),
+        vec![1u8],
+    ]);
+    assert_eq!(committed, vec![transaction(1, 1), transaction(3, 3)]);
+
+    // Replayed transactions are dropped.
+    let committed = filter.filter(vec![transaction(1, 1), transaction(4, 4)]);
+    assert_eq!(committed, vec![transaction(4, 4)]);
+}
+
+#[test]
+fn fresh_does_not_advance() {
+    let (sender, _) = keys().pop().unwrap();
+    let mut filter = NonceFilter::default();
+    assert!(filter.fresh(&sender, 1));
+    assert!(filter.fresh(&sender, 1));
+
+    // Only committed nonces advance the filter.
+    filter.filter(vec![transaction(1, 1)]);
+    assert!(!filter.fresh(&sender, 1));
+    assert!(filter.fresh(&sender, 2));
+}
+
+#[test]
+fn restore_committed_nonces() {
+    let mut filter = NonceFilter::default();
+    filter.filter(vec![transaction(2, 2)]);
+    let bytes = bincode::serialize(&filter).unwrap();
+
+    // A restarted node restores the filter it persisted.
+    let mut filter: NonceFilter = bincode::deserialize(&bytes).unwrap();
+    let (sender, _) = keys().pop().unwrap();
+    assert_eq!(filter.nonce(&sender), Some(2));
+    assert!(filter.filter(vec![transaction(1, 1)]).is_empty());
+}
@@ -0,0 +1,128 @@
+use super::*;
+use crate::common::keys;
+
+fn digest(i: u8) -> Digest {
+    Digest([i; 32])
+}
+
+#[test]
+fn fifo_per_author() {
+    let (author, _) = keys().pop().unwrap();
+    let mut queue = PayloadQueue::new(10_000);
+    for i in 0..3 {
+        assert!(queue.insert(digest(i), author));
+    }
+    assert!(!queue.insert(digest(0), author));
+    assert_eq!(queue.len(), 3);
+
+    let taken: Vec<_> = queue.take(3).into_iter().map(|(x, _)| x).collect();
+    assert_eq!(taken, vec![digest(0), digest(1), digest(2)]);
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn round_robin_across_authors() {
+    let mut keys = keys();
+    let (flooder, _) = keys.pop().unwrap();
+    let (honest, _) = keys.pop().unwrap();
+    let mut queue = PayloadQueue::new(10_000);
+    for i in 0..100 {
+        queue.insert(digest(i), flooder);
+    }
+    queue.insert(digest(100), honest);
+
+    // Both authors are served in every pair of slots.
+    let taken = queue.take(2);
+    assert!(taken.iter().any(|(_, author)| *author == flooder));
+    assert!(taken.iter().any(|(x, author)| *author == honest && *x == digest(100)));
+}
+
+#[test]
+fn starvation_freedom() {
+    let mut keys = keys();
+    let (flooder, _) = keys.pop().unwrap();
+    let (honest, _) = keys.pop().unwrap();
+    let mut queue = PayloadQueue::new(10_000);
+
+    // A payload inserted behind a flood is served within one round of authors,
+    // even if the flood keeps coming.
+    let mut next = 0;
+    for _ in 0..50 {
+        queue.insert(digest(next), flooder);
+        next += 1;
+    }
+    queue.insert(digest(200), honest);
+    let mut rounds = 0;
+    loop {
+        rounds += 1;
+        for _ in 0..5 {
+            queue.insert(digest(next), flooder);
+            next += 1;
+        }
+        let taken = queue.take(1);
+        if taken.iter().any(|(x, _)| *x == digest(200)) {
+            break;
+        }
+        assert!(rounds < 2);
+    }
+}
+
+#[test]
+fn aged_payloads_first() {
+    let mut keys = keys();
+    let (first, _) = keys.pop().unwrap();
+    let (second, _) = keys.pop().unwrap();
+
+    // With a zero max age, every payload is served in arrival order.
+    let mut queue = PayloadQueue::new(0);
+    queue.insert(digest(0), first);
+    queue.insert(digest(1), first);
+    queue.insert(digest(2), second);
+    queue.insert(digest(3), first);
+    let taken: Vec<_> = queue.take(4).into_iter().map(|(x, _)| x).collect();
+    assert_eq!(taken, vec![digest(0), digest(1), digest(2), digest(3)]);
+}
+
+#[test]
+fn remove() {
+    let (author, _) = keys().pop().unwrap();
+    let mut queue = PayloadQueue::new(10_000);
+    queue.insert(digest(0), author);
+    queue.insert(digest(1), author);
+    assert!(queue.remove(&digest(0)));
+    assert!(!queue.remove(&digest(0)));
+    assert!(!queue.contains(&digest(0)));
+
+    // A removed digest may be inserted again, and is then served after the others.
+    queue.insert(digest(0), author);
+    let taken: Vec<_> = queue.take(10).into_iter().map(|(x, _)| x).collect();
+    assert_eq!(taken, vec![digest(1), digest(0)]);
+}
+
+#[test]
+fn weighted_lanes() {
+    let (author, _) = keys().pop().unwrap();
+    let mut queue = LaneQueue::new(10_000, &[2, 1]);
+    for i in 0..10 {
+        queue.assign(digest(i), 1);
+        queue.insert(digest(i), author);
+    }
+    for i in 10..20 {
+        queue.assign(digest(i), 0);
+        queue.insert(digest(i), author);
+    }
+    assert_eq!(queue.len(), 20);
+
+    // The priority lane gets two slots for every slot of the bulk lane.
+    let taken: Vec<_> = queue.take(6).into_iter().map(|(x, _)| x).collect();
+    assert_eq!(
+        taken,
+        vec![digest(10), digest(11), digest(0), digest(12), digest(13), digest(1)]
+    );
+
+    // Unassigned payloads go to the last lane, and removing forgets the lane.
+    assert!(queue.remove(&digest(14)));
+    queue.insert(digest(14), author);
+    let taken: Vec<_> = queue.take(20).into_iter().map(|(x, _)| x).collect();
+    assert_eq!(taken.last(), Some(&digest(14)));
+}
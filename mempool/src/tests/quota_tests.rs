@@ -0,0 +1,112 @@
+use super::*;
+use crate::common::keys;
+
+fn digest(i: u8) -> Digest {
+    Digest([i; 32])
+}
+
+fn quotas(window_bytes: usize, window_payloads: usize, storage_bytes: usize) -> Quotas {
+    let parameters = Parameters {
+        quota_window: 60_000,
+        quota_window_bytes: window_bytes,
+        quota_window_payloads: window_payloads,
+        quota_storage_bytes: storage_bytes,
+        payload_expiry: 10,
+        ..Parameters::default()
+    };
+    Quotas::new(&parameters, Arc::new(Mutex::new(QuotaUsage::default())))
+}
+
+#[test]
+fn rate_quota() {
+    let mut keys = keys();
+    let (flooder, _) = keys.pop().unwrap();
+    let (honest, _) = keys.pop().unwrap();
+    let mut quotas = quotas(0, 2, 0);
+    assert!(quotas.charge(&digest(0), flooder, 10, 1).is_ok());
+    assert!(quotas.charge(&digest(1), flooder, 10, 1).is_ok());
+    match quotas.charge(&digest(2), flooder, 10, 1) {
+        Err(MempoolError::QuotaExceeded(author)) => assert_eq!(author, flooder),
+        _ => panic!("Unexpected result"),
+    }
+
+    // Other authors are not affected, and a payload is only charged once.
+    assert!(quotas.charge(&digest(3), honest, 10, 1).is_ok());
+    assert!(quotas.charge(&digest(0), flooder, 10, 1).is_ok());
+}
+
+#[test]
+fn quotas_shared_by_workers() {
+    let (author, _) = keys().pop().unwrap();
+    let parameters = Parameters {
+        quota_storage_bytes: 100,
+        ..Parameters::default()
+    };
+    let usage = Arc::new(Mutex::new(QuotaUsage::default()));
+    let mut worker_0 = Quotas::new(&parameters, usage.clone());
+    let mut worker_1 = Quotas::new(&parameters, usage);
+
+    // An author cannot store more by sending its payloads to several workers.
+    assert!(worker_0.charge(&digest(0), author, 60, 1).is_ok());
+    assert!(worker_1.charge(&digest(1), author, 60, 1).is_err());
+
+    // Each worker releases the payloads it stored.
+    worker_1.release(&[digest(0)]);
+    assert!(worker_1.charge(&digest(1), author, 60, 1).is_err());
+    worker_0.release(&[digest(0)]);
+    assert!(worker_1.charge(&digest(1), author, 60, 1).is_ok());
+}
+
+#[test]
+fn storage_released_on_commit() {
+    let (author, _) = keys().pop().unwrap();
+    let mut quotas = quotas(0, 0, 100);
+    assert!(quotas.charge(&digest(0), author, 60, 1).is_ok());
+    assert!(quotas.charge(&digest(1), author, 60, 1).is_err());
+
+    quotas.release(&[digest(0)]);
+    assert!(quotas.charge(&digest(1), author, 60, 2).is_ok());
+}
+
+#[test]
+fn storage_released_on_expiry() {
+    let (author, _) = keys().pop().unwrap();
+    let mut quotas = quotas(0, 0, 100);
+    assert!(quotas.charge(&digest(0), author, 60, 1).is_ok());
+    assert!(quotas.expire(10).is_empty());
+    assert_eq!(quotas.expire(11), vec![digest(0)]);
+    assert!(quotas.charge(&digest(1), author, 60, 11).is_ok());
+}
+
+#[test]
+fn acked_payloads_never_expire() {
+    let (author, _) = keys().pop().unwrap();
+    let mut quotas = quotas(0, 0, 100);
+    assert!(quotas.charge(&digest(0), author, 10, 1).is_ok());
+    quotas.keep(&digest(0));
+    assert!(quotas.expire(100).is_empty());
+
+    // They are released once committed.
+    quotas.release(&[digest(0)]);
+    assert!(quotas.charge(&digest(1), author, 100, 100).is_ok());
+}
+
+#[test]
+fn fragments_charged_to_payload_author() {
+    let (author, _) = keys().pop().unwrap();
+    let mut quotas = quotas(0, 2, 100);
+
+    // The fragments of a payload add up, and count as a single payload.
+    assert!(quotas.charge_fragment(&digest(0), author, 40, 1).is_ok());
+    assert!(quotas.charge_fragment(&digest(0), author, 40, 1).is_ok());
+    assert!(quotas.charge_fragment(&digest(0), author, 40, 1).is_err());
+    assert!(quotas.charge_fragment(&digest(1), author, 10, 1).is_ok());
+    assert!(quotas.charge_fragment(&digest(2), author, 1, 1).is_err());
+
+    // The rebuilt payload is not charged again, and releasing it frees its fragments.
+    assert!(quotas.charge(&digest(0), author, 80, 1).is_ok());
+    assert!(quotas.charge_fragment(&digest(1), author, 10, 1).is_ok());
+    assert!(quotas.charge_fragment(&digest(1), author, 1, 1).is_err());
+    quotas.release(&[digest(0)]);
+    assert!(quotas.charge_fragment(&digest(1), author, 70, 1).is_ok());
+}
@@ -0,0 +1,56 @@
+use super::*;
+use tokio::sync::mpsc::channel;
+
+#[tokio::test]
+async fn notify_subscribers() {
+    let mut receipts = Receipts::default();
+    let (tx_receipt, mut rx_receipt) = channel(10);
+    receipts.subscribe(transaction_digest(&vec![2u8]), tx_receipt);
+    assert!(!receipts.is_empty());
+
+    // The receipt reports the position of the transaction in the block.
+    let block = Digest::default();
+    receipts.notify(5, &block, &[vec![1u8], vec![2u8]]);
+    let receipt = match rx_receipt.recv().await {
+        Some(Reply::Committed(receipt)) => receipt,
+        x => panic!("Unexpected reply: {:?}", x),
+    };
+    assert_eq!(receipt.transaction, transaction_digest(&vec![2u8]));
+    assert_eq!(receipt.epoch, 5);
+    assert_eq!(receipt.block, block);
+    assert_eq!(receipt.position, 1);
+
+    // Each subscriber is notified once.
+    assert!(receipts.is_empty());
+}
+
+#[tokio::test]
+async fn forget_closed_connections() {
+    let mut receipts = Receipts::default();
+    let (tx_receipt, rx_receipt) = channel(10);
+    receipts.subscribe(transaction_digest(&vec![1u8]), tx_receipt);
+    drop(rx_receipt);
+
+    receipts.notify(1, &Digest::default(), &[vec![2u8]]);
+    assert!(receipts.is_empty());
+}
+
+#[tokio::test]
+async fn reply_once_queued_or_dropped() {
+    let mut receipts = Receipts::default();
+    let (tx_reply, mut rx_reply) = channel(10);
+    receipts.subscribe(transaction_digest(&vec![1u8]), tx_reply.clone());
+    receipts.subscribe(transaction_digest(&vec![2u8]), tx_reply);
+
+    // Queued transactions are acknowledged and still wait for their receipt.
+    receipts.accepted(&[vec![1u8]]);
+    let reply = rx_reply.recv().await.unwrap();
+    assert_eq!(reply, Reply::Accepted(transaction_digest(&vec![1u8])));
+
+    // Dropped transactions are throttled and forgotten.
+    receipts.dropped(&[vec![2u8]]);
+    assert_eq!(rx_reply.recv().await.unwrap(), Reply::Throttled);
+    receipts.notify(1, &Digest::default(), &[vec![2u8]]);
+    assert!(rx_reply.try_recv().is_err());
+    assert!(!receipts.is_empty());
+}
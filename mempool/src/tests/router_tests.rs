@@ -0,0 +1,54 @@
+use super::*;
+use tokio::sync::mpsc::channel;
+
+// A worker proposing its own digest and counting the cleanups it receives.
+fn worker(id: u8) -> (Sender<ConsensusMempoolMessage>, Receiver<EpochNumber>) {
+    let (tx_worker, mut rx_worker) = channel(10);
+    let (tx_cleanup, rx_cleanup) = channel(10);
+    tokio::spawn(async move {
+        while let Some(message) = rx_worker.recv().await {
+            match message {
+                ConsensusMempoolMessage::Get(_, _, sender) => {
+                    let _ = sender.send(vec![Digest([id; 32])]);
+                }
+                ConsensusMempoolMessage::Cleanup(_, epoch, _) => {
+                    tx_cleanup.send(epoch).await.unwrap();
+                }
+                _ => (),
+            }
+        }
+    });
+    (tx_worker, rx_cleanup)
+}
+
+async fn get(router: &Sender<ConsensusMempoolMessage>, max: usize) -> Vec<Digest> {
+    let (sender, receiver) = oneshot::channel();
+    let message = ConsensusMempoolMessage::Get(max, 1, sender);
+    router.send(message).await.unwrap();
+    receiver.await.unwrap()
+}
+
+#[tokio::test]
+async fn fill_blocks_from_all_workers() {
+    let (tx_router, rx_router) = channel(10);
+    let (worker_0, mut cleanup_0) = worker(0);
+    let (worker_1, mut cleanup_1) = worker(1);
+    Router::spawn(rx_router, vec![worker_0, worker_1]);
+
+    // Blocks get the digests of all workers, starting from a different worker every time.
+    assert_eq!(
+        get(&tx_router, 10).await,
+        vec![Digest([0; 32]), Digest([1; 32])]
+    );
+    assert_eq!(
+        get(&tx_router, 10).await,
+        vec![Digest([1; 32]), Digest([0; 32])]
+    );
+    assert_eq!(get(&tx_router, 1).await, vec![Digest([0; 32])]);
+
+    // All workers clean up.
+    let message = ConsensusMempoolMessage::Cleanup(Vec::new(), 3, Digest::default());
+    tx_router.send(message).await.unwrap();
+    assert_eq!(cleanup_0.recv().await, Some(3));
+    assert_eq!(cleanup_1.recv().await, Some(3));
+}
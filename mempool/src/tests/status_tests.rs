@@ -0,0 +1,39 @@
+use super::*;
+use crate::messages::transaction_digest;
+use std::fs;
+
+#[tokio::test]
+async fn trace_transaction() {
+    let path = ".db_test_trace_transaction";
+    let _ = fs::remove_dir_all(path);
+    let mut store = Store::new(path).unwrap();
+    let dedup = Mutex::new(DedupIndex::new(10));
+    let transaction = transaction_digest(&vec![1u8]);
+
+    // The transaction is unknown until a client submits it.
+    let status = transaction_status(&mut store, &dedup, &transaction).await;
+    assert_eq!(status.unwrap(), TransactionStatus::Unknown);
+    dedup.lock().unwrap().submit(transaction.clone());
+    let status = transaction_status(&mut store, &dedup, &transaction).await;
+    assert_eq!(status.unwrap(), TransactionStatus::Pending);
+
+    // Then it is put in a payload.
+    let payload = Digest([1u8; 32]);
+    let value = bincode::serialize(&payload).unwrap();
+    store.write(transaction_key(&transaction), value).await;
+    let status = transaction_status(&mut store, &dedup, &transaction).await;
+    assert_eq!(
+        status.unwrap(),
+        TransactionStatus::InPayload(payload.clone())
+    );
+
+    // And finally committed.
+    let (epoch, block): (EpochNumber, _) = (3, Digest([2u8; 32]));
+    let value = bincode::serialize(&(epoch, &block)).unwrap();
+    store.write(commit_key(&payload), value).await;
+    let status = transaction_status(&mut store, &dedup, &transaction).await;
+    assert_eq!(
+        status.unwrap(),
+        TransactionStatus::Committed { epoch, block }
+    );
+}
@@ -19,7 +19,7 @@ async fn verify_empty() {
         name,
         committee(),
         tx_network,
-        /* sync_retry_delay */ 10_000,
+        &Parameters::default(),
     );
     let result = synchronizer.verify_payload(block()).await;
     assert!(result.is_ok());
@@ -40,7 +40,7 @@ async fn verify_wait() {
         name,
         committee(),
         tx_network,
-        /* sync_retry_delay */ 10_000,
+        &Parameters::default(),
     );
 
     // Make a block with two payloads.
@@ -86,3 +86,205 @@ async fn verify_wait() {
     //     _ => assert!(false),
     // }
 }
+
+#[tokio::test]
+async fn evict_oldest_waiter() {
+    let (tx_consensus, mut rx_consensus) = channel(1);
+    let (tx_network, _rx_network) = channel(10);
+    let store_path = ".db_test_evict_oldest_waiter";
+    let _ = fs::remove_dir_all(store_path);
+    let mut store = Store::new(store_path).unwrap();
+    let (name, _) = keys().pop().unwrap();
+    let mut synchronizer = Synchronizer::new(
+        tx_consensus,
+        store.clone(),
+        name,
+        committee(),
+        tx_network,
+        &Parameters {
+            max_pending_syncs_per_author: 1,
+            ..Parameters::default()
+        },
+    );
+
+    // The same author sends two blocks with missing payloads.
+    let old = Block {
+        payload: vec![Digest([1u8; 32])],
+        epoch: 1,
+        ..block()
+    };
+    let new = Block {
+        payload: vec![Digest([2u8; 32])],
+        epoch: 2,
+        ..block()
+    };
+    assert!(!synchronizer.verify_payload(old).await.unwrap());
+    assert!(!synchronizer.verify_payload(new.clone()).await.unwrap());
+
+    // Ensure only the newest block is delivered once the payloads arrive.
+    store.write(vec![1u8; 32], Vec::new()).await;
+    store.write(vec![2u8; 32], Vec::new()).await;
+    match rx_consensus.recv().await {
+        Some(ConsensusMessage::Val(b)) => assert_eq!(b.digest(), new.digest()),
+        _ => assert!(false),
+    }
+}
+
+#[tokio::test]
+async fn flooding_author_cannot_evict_others() {
+    let (tx_consensus, mut rx_consensus) = channel(1);
+    let (tx_network, _rx_network) = channel(10);
+    let store_path = ".db_test_flooding_author_cannot_evict_others";
+    let _ = fs::remove_dir_all(store_path);
+    let mut store = Store::new(store_path).unwrap();
+    let mut keys = keys();
+    let (name, _) = keys.pop().unwrap();
+    let (honest, _) = keys.pop().unwrap();
+    let (flooder, _) = keys.pop().unwrap();
+    let mut synchronizer = Synchronizer::new(
+        tx_consensus,
+        store.clone(),
+        name,
+        committee(),
+        tx_network,
+        &Parameters {
+            max_pending_syncs_per_epoch: 2,
+            ..Parameters::default()
+        },
+    );
+
+    // An honest author sends one block, then another author floods the same epoch.
+    let block_of = |author, x: u8| Block {
+        author,
+        payload: vec![Digest([x; 32])],
+        epoch: 1,
+        ..block()
+    };
+    let honest_block = block_of(honest, 1);
+    let digest = honest_block.digest();
+    assert!(!synchronizer.verify_payload(honest_block).await.unwrap());
+    for x in 2..10 {
+        let flood = block_of(flooder, x);
+        assert!(!synchronizer.verify_payload(flood).await.unwrap());
+    }
+
+    // Ensure the honest block is still delivered once its payload arrives.
+    store.write(vec![1u8; 32], Vec::new()).await;
+    match rx_consensus.recv().await {
+        Some(ConsensusMessage::Val(b)) => assert_eq!(b.digest(), digest),
+        _ => assert!(false),
+    }
+}
+
+#[tokio::test]
+async fn reject_blocks_beyond_horizon() {
+    let (tx_consensus, _rx_consensus) = channel(1);
+    let (tx_network, mut rx_network) = channel(10);
+    let store_path = ".db_test_reject_blocks_beyond_horizon";
+    let _ = fs::remove_dir_all(store_path);
+    let store = Store::new(store_path).unwrap();
+    let (name, _) = keys().remove(0);
+    let mut synchronizer = Synchronizer::new(
+        tx_consensus,
+        store,
+        name,
+        committee(),
+        tx_network,
+        &Parameters {
+            max_sync_requests_per_author: 1,
+            sync_epoch_horizon: 10,
+            ..Parameters::default()
+        },
+    );
+    synchronizer.cleanup(5).await;
+
+    // Blocks too far ahead, and blocks needing too many requests, are not fetched.
+    let far = Block {
+        payload: vec![Digest([1u8; 32])],
+        epoch: 16,
+        ..block()
+    };
+    assert!(!synchronizer.verify_payload(far).await.unwrap());
+    let large = Block {
+        payload: vec![Digest([2u8; 32]), Digest([3u8; 32])],
+        epoch: 6,
+        ..block()
+    };
+    assert!(!synchronizer.verify_payload(large).await.unwrap());
+
+    // Ensure only the block within bounds is fetched.
+    let near = Block {
+        payload: vec![Digest([4u8; 32])],
+        epoch: 15,
+        ..block()
+    };
+    assert!(!synchronizer.verify_payload(near).await.unwrap());
+    match rx_network.recv().await {
+        Some(NetMessage(bytes, _)) => match bincode::deserialize(&bytes).unwrap() {
+            MempoolMessage::PayloadRequest(p, _) => assert_eq!(p, vec![Digest([4u8; 32])]),
+            _ => assert!(false),
+        },
+        _ => assert!(false),
+    }
+}
+
+#[tokio::test]
+async fn rotate_on_timeout() {
+    let (tx_consensus, _rx_consensus) = channel(1);
+    let (tx_network, mut rx_network) = channel(10);
+    let store_path = ".db_test_rotate_on_timeout";
+    let _ = fs::remove_dir_all(store_path);
+    let store = Store::new(store_path).unwrap();
+    let (name, _) = keys().remove(0);
+    let mut synchronizer = Synchronizer::new(
+        tx_consensus,
+        store,
+        name,
+        committee(),
+        tx_network,
+        &Parameters {
+            sync_retry_delay: 50,
+            ..Parameters::default()
+        },
+    );
+
+    // Make a block with a missing payload.
+    let block = Block {
+        payload: vec![Digest([1u8; 32])],
+        epoch: 1,
+        ..block()
+    };
+    let author = block.author;
+    assert!(!synchronizer.verify_payload(block).await.unwrap());
+
+    // Ensure we first ask the block author, and then another peer (never ourselves).
+    let author_address = committee().mempool_address(&author).unwrap();
+    let own_address = committee().mempool_address(&name).unwrap();
+    match rx_network.recv().await {
+        Some(NetMessage(_, recipients)) => assert_eq!(recipients, vec![author_address]),
+        _ => assert!(false),
+    }
+    let other_address = match rx_network.recv().await {
+        Some(NetMessage(_, recipients)) => {
+            assert_eq!(recipients.len(), 1);
+            assert!(recipients[0] != author_address && recipients[0] != own_address);
+            recipients[0]
+        }
+        _ => panic!("Expected a payload request"),
+    };
+
+    // Ensure a peer that consensus saw echo the block is asked before the others.
+    let (holder, _) = keys()
+        .into_iter()
+        .find(|(x, _)| {
+            let address = committee().mempool_address(x).unwrap();
+            *x != name && *x != author && address != other_address
+        })
+        .unwrap();
+    synchronizer.holders(vec![Digest([1u8; 32])], holder).await;
+    let holder_address = committee().mempool_address(&holder).unwrap();
+    match rx_network.recv().await {
+        Some(NetMessage(_, recipients)) => assert_eq!(recipients, vec![holder_address]),
+        _ => assert!(false),
+    }
+}
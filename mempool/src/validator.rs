@@ -0,0 +1,63 @@
+use crate::messages::{SignedTransaction, Transaction};
+use std::sync::Arc;
+
+// Application rules checked on every transaction before it enters the mempool, both when a
+// client submits it and when it comes in a payload of another node. Validation must be
+// deterministic, or honest nodes may reject each other's payloads.
+pub trait TransactionValidator: Send + Sync + 'static {
+    // Returns the reason why the transaction is invalid, if it is.
+    fn validate(&self, transaction: &Transaction) -> Result<(), String>;
+
+    // The priority lane of the transaction, lane 0 being served first. Like validation, it
+    // must be deterministic.
+    fn lane(&self, _transaction: &Transaction) -> usize {
+        0
+    }
+}
+
+// The lane of a payload is the highest priority lane of its transactions.
+pub fn payload_lane(
+    validator: &dyn TransactionValidator,
+    transactions: &[Transaction],
+    lanes: usize,
+) -> usize {
+    transactions
+        .iter()
+        .map(|x| validator.lane(x))
+        .min()
+        .unwrap_or(lanes)
+        .min(lanes - 1)
+}
+
+// Accepts any transaction.
+pub struct AcceptAll;
+
+impl TransactionValidator for AcceptAll {
+    fn validate(&self, _transaction: &Transaction) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+// Verifies the envelope of signed client transactions, and applies the rules of the
+// application to their payload.
+pub struct SignedValidator {
+    inner: Arc<dyn TransactionValidator>,
+}
+
+impl SignedValidator {
+    pub fn new(inner: Arc<dyn TransactionValidator>) -> Self {
+        Self { inner }
+    }
+}
+
+impl TransactionValidator for SignedValidator {
+    fn validate(&self, transaction: &Transaction) -> Result<(), String> {
+        let signed = SignedTransaction::from_transaction(transaction).map_err(|e| e.to_string())?;
+        signed.verify().map_err(|e| e.to_string())?;
+        self.inner.validate(&signed.payload)
+    }
+
+    fn lane(&self, transaction: &Transaction) -> usize {
+        SignedTransaction::from_transaction(transaction).map_or(0, |x| self.inner.lane(&x.payload))
+    }
+}
@@ -0,0 +1,87 @@
+use crate::pipeline::CommittedBatch;
+use crypto::Digest;
+use ed25519_dalek::Digest as _;
+use ed25519_dalek::Sha512;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+
+#[cfg(test)]
+#[path = "tests/application_tests.rs"]
+pub mod application_tests;
+
+// The store key of the persisted application state.
+pub const STATE_KEY: &[u8] = b"APPLICATION_STATE";
+
+// A deterministic state machine replicated by executing the committed batches in order.
+pub trait Application: Send + 'static {
+    // Apply the transactions of the next committed batch and return the new state root.
+    fn execute(&mut self, batch: &CommittedBatch) -> Digest;
+
+    // Answer a read-only query against the current state.
+    fn query(&self, request: &[u8]) -> Option<Vec<u8>>;
+
+    // Serialize the current state, so that a replica can restore it instead of replaying
+    // the whole chain.
+    fn snapshot(&self) -> Vec<u8>;
+
+    fn restore(&mut self, snapshot: &[u8]) -> Result<(), String>;
+}
+
+// The transactions understood by the key-value application.
+#[derive(Serialize, Deserialize, Debug)]
+pub enum KeyValueCommand {
+    Set(Vec<u8>, Vec<u8>),
+    Delete(Vec<u8>),
+}
+
+// A reference application: a key-value map, queried by key. Transactions that are not
+// valid commands are ignored.
+#[derive(Default)]
+pub struct KeyValueStore {
+    state: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl KeyValueStore {
+    // The hash of all entries, in key order.
+    pub fn root(&self) -> Digest {
+        let mut hasher = Sha512::new();
+        for (key, value) in &self.state {
+            hasher.update((key.len() as u64).to_le_bytes());
+            hasher.update(key);
+            hasher.update((value.len() as u64).to_le_bytes());
+            hasher.update(value);
+        }
+        Digest(hasher.finalize().as_slice()[..32].try_into().unwrap())
+    }
+}
+
+impl Application for KeyValueStore {
+    fn execute(&mut self, batch: &CommittedBatch) -> Digest {
+        for transaction in &batch.transactions {
+            match bincode::deserialize(transaction) {
+                Ok(KeyValueCommand::Set(key, value)) => {
+                    self.state.insert(key, value);
+                }
+                Ok(KeyValueCommand::Delete(key)) => {
+                    self.state.remove(&key);
+                }
+                Err(_) => (),
+            }
+        }
+        self.root()
+    }
+
+    fn query(&self, request: &[u8]) -> Option<Vec<u8>> {
+        self.state.get(request).cloned()
+    }
+
+    fn snapshot(&self) -> Vec<u8> {
+        bincode::serialize(&self.state).expect("Failed to serialize state")
+    }
+
+    fn restore(&mut self, snapshot: &[u8]) -> Result<(), String> {
+        self.state = bincode::deserialize(snapshot).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
@@ -5,7 +5,9 @@ use clap::{crate_name, crate_version, App, AppSettings};
 use env_logger::Env;
 use futures::future::join_all;
 use futures::sink::SinkExt as _;
-use log::{info, warn};
+use futures::stream::StreamExt as _;
+use log::{debug, info, warn};
+use mempool::Reply;
 use rand::Rng;
 use std::net::SocketAddr;
 use tokio::net::TcpStream;
@@ -105,10 +107,25 @@ impl Client {
         let mut tx = BytesMut::with_capacity(self.size);
         let mut counter = 0;
         let mut r = rand::thread_rng().gen();
-        let mut transport = Framed::new(stream, LengthDelimitedCodec::new());
+        let (mut transport, mut replies) = Framed::new(stream, LengthDelimitedCodec::new()).split();
         let interval = interval(Duration::from_millis(BURST_DURATION));
         tokio::pin!(interval);
 
+        // Drain the acknowledgements and receipts of the node, otherwise it stops reading our transactions.
+        tokio::spawn(async move {
+            while let Some(Ok(bytes)) = replies.next().await {
+                match bincode::deserialize(&bytes) {
+                    Ok(Reply::Accepted(_)) => (),
+                    Ok(Reply::Committed(receipt)) => debug!(
+                        "Transaction {} committed in block {} of epoch {}",
+                        receipt.transaction, receipt.block, receipt.epoch
+                    ),
+                    Ok(reply) => debug!("Transaction not accepted: {:?}", reply),
+                    Err(e) => warn!("Failed to deserialize reply: {}", e),
+                }
+            }
+        });
+
         // NOTE: This log entry is used to compute performance.
         info!("Start sending transactions");
 
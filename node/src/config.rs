@@ -9,6 +9,11 @@ use serde::{Deserialize, Serialize};
 use std::fs::{self, OpenOptions};
 use std::io::BufWriter;
 use std::io::Write as _;
+use std::net::SocketAddr;
+
+#[cfg(test)]
+#[path = "tests/config_tests.rs"]
+pub mod config_tests;
 
 pub trait Export: Serialize + DeserializeOwned {
     fn read(path: &str) -> Result<Self, NodeError> {
@@ -38,11 +43,47 @@ pub trait Export: Serialize + DeserializeOwned {
     }
 }
 
-#[derive(Serialize, Deserialize, Default)]
+#[derive(Serialize, Deserialize)]
 pub struct Parameters {
     pub consensus: ConsensusParameters,
     pub mempool: MempoolParameters,
     pub protocol: u8,
+    // Where clients query the application state, if anywhere.
+    #[serde(default)]
+    pub query_address: Option<SocketAddr>,
+    // How many epochs the node executes between two snapshots of the application state. It
+    // replays the blocks committed since the last snapshot after a restart.
+    #[serde(default = "Parameters::default_snapshot_interval")]
+    pub snapshot_interval: u64,
+}
+
+impl Default for Parameters {
+    fn default() -> Self {
+        Self {
+            consensus: ConsensusParameters::default(),
+            mempool: MempoolParameters::default(),
+            protocol: 0,
+            query_address: None,
+            snapshot_interval: Self::default_snapshot_interval(),
+        }
+    }
+}
+
+impl Parameters {
+    fn default_snapshot_interval() -> u64 {
+        100
+    }
+
+    // Consensus proposes the availability certificates made by the mempool: both must use
+    // them, or neither.
+    pub fn validate(&self) -> Result<(), NodeError> {
+        if self.consensus.availability_certificates != self.mempool.availability_certificates {
+            return Err(NodeError::InvalidParameters(
+                "consensus and mempool disagree on availability certificates".to_string(),
+            ));
+        }
+        Ok(())
+    }
 }
 
 impl Export for Parameters {}
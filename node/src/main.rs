@@ -1,6 +1,10 @@
+mod application;
 mod config;
 mod node;
+mod pipeline;
+mod query;
 
+use crate::application::KeyValueStore;
 use crate::config::Export as _;
 use crate::config::{Committee, Secret};
 use crate::node::Node;
@@ -9,7 +13,7 @@ use consensus::Committee as ConsensusCommittee;
 use crypto::SecretShare;
 use env_logger::Env;
 use futures::future::join_all;
-use log::error;
+use log::{error, info};
 use mempool::Committee as MempoolCommittee;
 use threshold_crypto::SecretKeySet;
 use threshold_crypto::serde_impl::SerdeSecret;
@@ -41,6 +45,13 @@ async fn main() {
                 .args_from_usage("--parameters=[FILE] 'The file containing the node parameters'")
                 .args_from_usage("--store=<PATH> 'The path where to create the data store'"),
         )
+        .subcommand(
+            SubCommand::with_name("audit")
+                .about("Checks the chain of committed blocks of a data store")
+                .args_from_usage("--threshold_keys=<FILE> 'The file containing the node threshold_keys'")
+                .args_from_usage("--committee=<FILE> 'The file containing committee information'")
+                .args_from_usage("--store=<PATH> 'The path of the data store'"),
+        )
         .subcommand(
             SubCommand::with_name("deploy")
                 .about("Deploys a network of nodes locally")
@@ -83,14 +94,26 @@ async fn main() {
             match Node::new(committee_file, key_file, threshold_key_file, store_path, parameters_file).await {
                 Ok(mut node) => {
                     tokio::spawn(async move {
-                        node.analyze_block().await;
+                        node.execute(&mut KeyValueStore::default()).await;
                     })
                     .await
-                    .expect("Failed to analyze committed blocks");
+                    .expect("Failed to execute committed blocks");
                 }
                 Err(e) => error!("{}", e),
             }
         }
+        ("audit", Some(subm)) => {
+            let threshold_key_file = subm.value_of("threshold_keys").unwrap();
+            let committee_file = subm.value_of("committee").unwrap();
+            let store_path = subm.value_of("store").unwrap();
+            match Node::audit(committee_file, threshold_key_file, store_path).await {
+                Ok(epoch) => info!("Committed chain is valid up to epoch {}", epoch),
+                Err(e) => {
+                    error!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
         ("deploy", Some(subm)) => {
             let nodes = subm.value_of("nodes").unwrap();
             match nodes.parse::<usize>() {
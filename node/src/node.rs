@@ -1,15 +1,26 @@
+use crate::application::{Application, STATE_KEY};
 use crate::config::Export as _;
 use crate::config::{Committee, Parameters, Secret};
-use consensus::{Block, Consensus, ConsensusError};
-use crypto::{SignatureService, SecretShare};
-use log::{info, warn};
-use mempool::{Mempool, MempoolError};
+use crate::pipeline::{CommittedBatch, Pipeline};
+use crate::query::{Query, QueryServer};
+use consensus::{
+    audit_chain, Block, CommittedBlock, Consensus, ConsensusError, EpochNumber, PacingMetrics,
+};
+use crypto::Hash as _;
+use crypto::{Digest, SignatureService, SecretShare};
+use log::{debug, error, info};
+use mempool::{AcceptAll, BatchingMetrics, ExecutionState, Mempool, MempoolError, NonceFilter};
 use store::{Store, StoreError};
+use std::sync::{Arc, Mutex};
 use thiserror::Error;
 use tokio::sync::mpsc::{channel, Receiver};
 use threshold_crypto::SecretKeySet;
 use threshold_crypto::serde_impl::SerdeSecret;
 
+#[cfg(test)]
+#[path = "tests/node_tests.rs"]
+pub mod node_tests;
+
 #[derive(Error, Debug)]
 pub enum NodeError {
     #[error("Failed to read config file '{file}': {message}")]
@@ -26,10 +37,26 @@ pub enum NodeError {
 
     #[error(transparent)]
     MempoolError(#[from] MempoolError),
+
+    #[error("Invalid parameters: {0}")]
+    InvalidParameters(String),
+
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] Box<bincode::ErrorKind>),
+
+    #[error("Application state of epoch {0} does not match the committed chain")]
+    StateMismatch(EpochNumber),
 }
 
 pub struct Node {
-    pub commit: Receiver<Block>,
+    pub commit: Receiver<CommittedBatch>,
+    pub pacing: PacingMetrics,
+    pub batching: BatchingMetrics,
+    store: Store,
+    queries: Receiver<Query>,
+    // How far the persisted state goes, and the last nonce executed from every sender.
+    execution: ExecutionState,
+    snapshot_interval: EpochNumber,
 }
 
 impl Node {
@@ -41,8 +68,10 @@ impl Node {
         parameters: Option<&str>,
     ) -> Result<Self, NodeError> {
         let (tx_commit, rx_commit) = channel(10000);
+        let (tx_batch, rx_batch) = channel(10000);
         let (tx_consensus, rx_consensus) = channel(10000);
         let (tx_consensus_mempool, rx_consensus_mempool) = channel(10000);
+        let (tx_query, rx_query) = channel(10000);
 
         // Read the committee and secret key from file.
         let committee = Committee::read(committee_file)?;
@@ -54,18 +83,49 @@ impl Node {
         let pk_set = tss_keys.pkset.clone();
 
         // Load default parameters if none are specified.
-        let parameters = match parameters {
+        let mut parameters = match parameters {
             Some(filename) => Parameters::read(filename)?,
             None => Parameters::default(),
         };
+        parameters.validate()?;
 
-        // Make the data store.
-        let store = Store::new(store_path)?;
+        // The mempool keeps the committed payloads as long as consensus keeps their blocks.
+        parameters.mempool.retention_depth = parameters.consensus.retention_depth;
+
+        // Make the data store, and resume from the application state we persisted.
+        let mut store = Store::new(store_path)?;
+        let executed = Self::checkpoint(&mut store).await?;
 
         // Run the signature service.
         let signature_service = SignatureService::new(secret_key, tss_keys.secret.into_inner());
 
+        // Answer the queries of clients against the application state.
+        if let Some(address) = parameters.query_address {
+            QueryServer::spawn(address, tx_query);
+        }
+
+        // Resolve the payloads of the committed blocks. We drop the signed transactions with
+        // stale nonces as we execute them, and the mempool rejects them upfront.
+        let nonces = match parameters.mempool.signed_transactions {
+            true => Some(Arc::new(Mutex::new(NonceFilter::default()))),
+            false => None,
+        };
+        let execution = ExecutionState {
+            nonces,
+            ..ExecutionState::default()
+        };
+        execution.progress.advance(executed);
+        Pipeline::spawn(
+            store.clone(),
+            rx_commit,
+            tx_batch,
+            parameters.mempool.dedup_window,
+            executed,
+            execution.clone(),
+        );
+
         // Make a new mempool.
+        let batching = BatchingMetrics::default();
         Mempool::run(
             name,
             committee.mempool,
@@ -74,9 +134,13 @@ impl Node {
             signature_service.clone(),
             tx_consensus.clone(),
             rx_consensus_mempool,
+            Arc::new(AcceptAll),
+            batching.clone(),
+            execution.clone(),
         )?;
 
         // Run the consensus core.
+        let pacing = PacingMetrics::default();
         Consensus::run(
             name,
             committee.consensus,
@@ -88,11 +152,33 @@ impl Node {
             rx_consensus,
             tx_consensus_mempool,
             tx_commit,
+            pacing.clone(),
         )
         .await?;
 
         info!("Node {} successfully booted", name);
-        Ok(Self { commit: rx_commit })
+        Ok(Self {
+            commit: rx_batch,
+            pacing,
+            batching,
+            store,
+            queries: rx_query,
+            execution,
+            snapshot_interval: parameters.snapshot_interval as EpochNumber,
+        })
+    }
+
+    // Audit the chain of committed blocks persisted in a data store.
+    pub async fn audit(
+        committee_file: &str,
+        tss_file: &str,
+        store_path: &str,
+    ) -> Result<EpochNumber, NodeError> {
+        let committee = Committee::read(committee_file)?;
+        let pk_set = SecretShare::read(tss_file)?.pkset;
+        let mut store = Store::new(store_path)?;
+        let epoch = audit_chain(&mut store, &committee.consensus, &pk_set).await?;
+        Ok(epoch)
     }
 
     pub fn print_key_file(filename: &str) -> Result<(), NodeError> {
@@ -115,9 +201,126 @@ impl Node {
         return Ok(());
     }
 
-    pub async fn analyze_block(&mut self) {
-        while let Some(_block) = self.commit.recv().await {
-            // This is where we can further process committed block.
+    // The epoch of the application state we persisted, if any. We refuse to resume from a state
+    // that does not reflect the committed chain of our store, e.g. after the chain was reset.
+    async fn checkpoint(store: &mut Store) -> Result<EpochNumber, NodeError> {
+        let bytes = match store.read(STATE_KEY.to_vec()).await? {
+            Some(x) => x,
+            None => return Ok(0),
+        };
+        let (epoch, digest, _, _): (EpochNumber, Digest, Vec<u8>, Option<NonceFilter>) =
+            bincode::deserialize(&bytes)?;
+        let block: Block = match store.read(CommittedBlock::key(epoch)).await? {
+            Some(bytes) => bincode::deserialize(&bytes)?,
+            None => return Err(NodeError::StateMismatch(epoch)),
+        };
+        if block.digest() != digest {
+            return Err(NodeError::StateMismatch(epoch));
+        }
+        Ok(epoch)
+    }
+
+    // Restore the application and the nonces from their persisted state, if any, and return
+    // the epoch of the last batch they reflect.
+    async fn restore(&mut self, application: &mut dyn Application) -> EpochNumber {
+        let bytes = match self.store.read(STATE_KEY.to_vec()).await {
+            Ok(Some(x)) => x,
+            Ok(None) => return 0,
+            Err(e) => {
+                error!("{}", e);
+                return 0;
+            }
+        };
+        let (epoch, _, snapshot, filter): (EpochNumber, Digest, Vec<u8>, Option<NonceFilter>) =
+            match bincode::deserialize(&bytes) {
+                Ok(x) => x,
+                Err(e) => {
+                    error!("Store corrupted. {}", e);
+                    return 0;
+                }
+            };
+        match application.restore(&snapshot) {
+            Ok(()) => {
+                if let (Some(nonces), Some(filter)) = (&self.execution.nonces, filter) {
+                    *nonces.lock().unwrap() = filter;
+                }
+                info!("Application state restored at epoch {}", epoch);
+                epoch
+            }
+            Err(e) => {
+                error!("Failed to restore application state: {}", e);
+                0
+            }
+        }
+    }
+
+    // Persist the application state with the block of its last batch, so that a restart only
+    // replays the blocks committed since. The nonces go with it, so that both reflect the same
+    // batches. The mempool keeps the payloads of these blocks until then.
+    async fn persist(&mut self, application: &dyn Application, epoch: EpochNumber, block: &Digest) {
+        let value = {
+            let nonces = self.execution.nonces.as_ref().map(|x| x.lock().unwrap());
+            bincode::serialize(&(epoch, block, application.snapshot(), nonces.as_deref()))
+                .expect("Failed to serialize application state")
+        };
+        self.store.write(STATE_KEY.to_vec(), value).await;
+        self.execution.progress.advance(epoch);
+    }
+
+    // Drive the application with the committed batches, in order, and answer the queries of
+    // clients in between. The state is persisted every `snapshot_interval` epochs, and once
+    // there are no more batches.
+    pub async fn execute(&mut self, application: &mut dyn Application) {
+        let executed = self.restore(application).await;
+        let mut persisted = executed;
+        let mut pending = None;
+        loop {
+            tokio::select! {
+                batch = self.commit.recv() => {
+                    let mut batch = match batch {
+                        Some(x) => x,
+                        None => break,
+                    };
+                    // Skip the batches already reflected in the restored state.
+                    if batch.epoch <= executed {
+                        continue;
+                    }
+                    // Drop the signed transactions replayed or reordered by their sender.
+                    if let Some(nonces) = &self.execution.nonces {
+                        let transactions = std::mem::take(&mut batch.transactions);
+                        batch.transactions = nonces.lock().unwrap().filter(transactions);
+                    }
+                    let root = application.execute(&batch);
+                    if batch.epoch >= persisted + self.snapshot_interval {
+                        self.persist(application, batch.epoch, &batch.block_digest).await;
+                        persisted = batch.epoch;
+                        pending = None;
+                    } else {
+                        pending = Some((batch.epoch, batch.block_digest.clone()));
+                    }
+                    debug!(
+                        "State root after epoch {}: {} ({} empty epochs proposed, {} avoided)",
+                        batch.epoch,
+                        root,
+                        self.pacing.empty_epochs(),
+                        self.pacing.empty_epochs_avoided()
+                    );
+                    debug!(
+                        "Batching after epoch {}: latency {} ms, payload size {} B, block delay {} ms, block size {} payloads",
+                        batch.epoch,
+                        self.batching.latency(),
+                        self.batching.payload_size(),
+                        self.batching.block_delay(),
+                        self.batching.block_size()
+                    );
+                },
+                Some((request, sender)) = self.queries.recv() => {
+                    let _ = sender.send(application.query(&request));
+                }
+            }
+        }
+        if let Some((epoch, block)) = pending {
+            self.persist(application, epoch, &block).await;
         }
     }
 }
@@ -0,0 +1,173 @@
+use consensus::{Block, CommittedBlock, EpochNumber};
+use crypto::Digest;
+use crypto::Hash as _;
+use log::{error, warn};
+use mempool::{CommitFilter, ExecutionState, Payload, Transaction};
+use serde::de::DeserializeOwned;
+use store::Store;
+use tokio::sync::mpsc::{Receiver, Sender};
+
+#[cfg(test)]
+#[path = "tests/pipeline_tests.rs"]
+pub mod pipeline_tests;
+
+// The transactions committed by a block, in order, without the ones committed recently.
+#[derive(Debug)]
+pub struct CommittedBatch {
+    pub epoch: EpochNumber,
+    pub block_digest: Digest,
+    pub transactions: Vec<Transaction>,
+}
+
+// Resolves the payloads of the committed blocks, waiting for the ones not stored yet. After a
+// restart, consensus resumes from its last committed block while the application resumes from
+// the last state it persisted: the pipeline replays the blocks committed in between from the
+// store.
+pub struct Pipeline {
+    store: Store,
+    rx_commit: Receiver<CommittedBlock>,
+    tx_batch: Sender<CommittedBatch>,
+    dedup_window: EpochNumber,
+    filter: CommitFilter,
+    execution: ExecutionState,
+    next: EpochNumber, // the epoch of the next block to resolve
+}
+
+impl Pipeline {
+    pub fn spawn(
+        store: Store,
+        rx_commit: Receiver<CommittedBlock>,
+        tx_batch: Sender<CommittedBatch>,
+        dedup_window: u64,
+        executed: EpochNumber,
+        execution: ExecutionState,
+    ) {
+        tokio::spawn(async move {
+            Self {
+                store,
+                rx_commit,
+                tx_batch,
+                dedup_window: dedup_window as EpochNumber,
+                filter: CommitFilter::new(dedup_window),
+                execution,
+                next: executed + 1,
+            }
+            .run()
+            .await;
+        });
+    }
+
+    async fn read<T: DeserializeOwned>(&mut self, key: Vec<u8>) -> Option<T> {
+        match self.store.read(key).await {
+            Ok(Some(bytes)) => match bincode::deserialize(&bytes) {
+                Ok(x) => Some(x),
+                Err(e) => {
+                    error!("Store corrupted. {}", e);
+                    None
+                }
+            },
+            Ok(None) => None,
+            Err(e) => {
+                error!("{}", e);
+                None
+            }
+        }
+    }
+
+    // Rebuild the filter from the blocks committed in the window of the first epoch we resolve,
+    // so that we keep filtering the transactions committed before a restart.
+    async fn recover(&mut self, next: EpochNumber) {
+        let first = (next + 1).saturating_sub(self.dedup_window).max(1);
+        for epoch in first..next {
+            let block: Block = match self.read(CommittedBlock::key(epoch)).await {
+                Some(block) => block,
+                None => continue,
+            };
+            let mut transactions = Vec::new();
+            for digest in &block.payload {
+                match self.read::<Payload>(digest.to_vec()).await {
+                    Some(payload) => transactions.extend(payload.transactions),
+                    None => warn!("Payload {} of epoch {} is not stored", digest, epoch),
+                }
+            }
+            self.filter.filter(epoch, transactions);
+        }
+    }
+
+    async fn resolve(&mut self, block: Block) -> Option<CommittedBatch> {
+        let mut transactions = Vec::new();
+        for digest in &block.payload {
+            let bytes = match self.store.notify_read(digest.to_vec()).await {
+                Ok(x) => x,
+                Err(e) => {
+                    error!("{}", e);
+                    return None;
+                }
+            };
+            match bincode::deserialize::<Payload>(&bytes) {
+                Ok(payload) => transactions.extend(payload.transactions),
+                Err(e) => {
+                    error!("Store corrupted. {}", e);
+                    return None;
+                }
+            }
+        }
+        // Clients learn where their transactions were committed.
+        let block_digest = block.digest();
+        self.execution
+            .receipts
+            .lock()
+            .unwrap()
+            .notify(block.epoch, &block_digest, &transactions);
+
+        let transactions = self.filter.filter(block.epoch, transactions);
+        Some(CommittedBatch {
+            epoch: block.epoch,
+            block_digest,
+            transactions,
+        })
+    }
+
+    // Resolve a block and hand its batch to the node. Returns false if the node is gone.
+    async fn deliver(&mut self, block: Block) -> bool {
+        self.next = block.epoch + 1;
+        match self.resolve(block).await {
+            Some(batch) => self.tx_batch.send(batch).await.is_ok(),
+            None => true,
+        }
+    }
+
+    // Resolve the blocks committed before `epoch` that we did not resolve yet, as long as they
+    // are stored. Returns false if the node is gone.
+    async fn replay(&mut self, epoch: EpochNumber) -> bool {
+        while self.next < epoch {
+            match self.read::<Block>(CommittedBlock::key(self.next)).await {
+                Some(block) => {
+                    if !self.deliver(block).await {
+                        return false;
+                    }
+                }
+                None => break,
+            }
+        }
+        true
+    }
+
+    async fn run(&mut self) {
+        self.recover(self.next).await;
+        if !self.replay(EpochNumber::MAX).await {
+            return;
+        }
+        while let Some(committed) = self.rx_commit.recv().await {
+            // Skip the blocks we replayed already, and catch up with the ones consensus
+            // committed before we started listening.
+            let epoch = committed.block.epoch;
+            if epoch < self.next {
+                continue;
+            }
+            if !self.replay(epoch).await || !self.deliver(committed.block).await {
+                break;
+            }
+        }
+    }
+}
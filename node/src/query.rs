@@ -0,0 +1,69 @@
+use bytes::Bytes;
+use futures::sink::SinkExt as _;
+use futures::stream::StreamExt as _;
+use log::{debug, warn};
+use std::net::SocketAddr;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::oneshot;
+use tokio_util::codec::{Framed, LengthDelimitedCodec};
+
+// A read-only request against the application state, with the channel where to send the answer.
+pub type Query = (Vec<u8>, oneshot::Sender<Option<Vec<u8>>>);
+
+// Serves the queries of clients. Every frame received on a connection is a request, answered
+// on the same connection with the serialized `Option<Vec<u8>>` of the application.
+pub struct QueryServer;
+
+impl QueryServer {
+    pub fn spawn(address: SocketAddr, tx_query: Sender<Query>) {
+        tokio::spawn(async move {
+            let listener = TcpListener::bind(&address)
+                .await
+                .expect("Failed to bind to TCP port");
+
+            debug!("Listening for client queries on {}", address);
+            loop {
+                let (socket, peer) = match listener.accept().await {
+                    Ok(value) => value,
+                    Err(e) => {
+                        warn!("Failed to connect with client: {}", e);
+                        continue;
+                    }
+                };
+                debug!("Connection established with client {}", peer);
+                Self::spawn_worker(socket, peer, tx_query.clone());
+            }
+        });
+    }
+
+    fn spawn_worker(socket: TcpStream, peer: SocketAddr, tx_query: Sender<Query>) {
+        tokio::spawn(async move {
+            let mut transport = Framed::new(socket, LengthDelimitedCodec::new());
+            while let Some(frame) = transport.next().await {
+                let request = match frame {
+                    Ok(x) => x.to_vec(),
+                    Err(e) => {
+                        warn!("Failed to receive client query: {}", e);
+                        return;
+                    }
+                };
+                let (sender, receiver) = oneshot::channel();
+                if tx_query.send((request, sender)).await.is_err() {
+                    // The node stopped executing the committed batches.
+                    return;
+                }
+                let answer = match receiver.await {
+                    Ok(x) => x,
+                    Err(_) => return,
+                };
+                let bytes = bincode::serialize(&answer).expect("Failed to serialize answer");
+                if let Err(e) = transport.send(Bytes::from(bytes)).await {
+                    warn!("Failed to answer client {}: {}", peer, e);
+                    return;
+                }
+            }
+            debug!("Connection closed by client {}", peer);
+        });
+    }
+}
@@ -0,0 +1,63 @@
+use super::*;
+
+fn set(key: u8, value: u8) -> Vec<u8> {
+    bincode::serialize(&KeyValueCommand::Set(vec![key], vec![value])).unwrap()
+}
+
+fn delete(key: u8) -> Vec<u8> {
+    bincode::serialize(&KeyValueCommand::Delete(vec![key])).unwrap()
+}
+
+fn batches() -> Vec<CommittedBatch> {
+    vec![
+        CommittedBatch {
+            epoch: 1,
+            block_digest: Digest([1u8; 32]),
+            transactions: vec![set(1, 1), set(2, 2), vec![0u8]],
+        },
+        CommittedBatch {
+            epoch: 2,
+            block_digest: Digest([2u8; 32]),
+            transactions: vec![delete(1), set(2, 3)],
+        },
+    ]
+}
+
+#[test]
+fn replicas_agree() {
+    let mut replicas = [KeyValueStore::default(), KeyValueStore::default()];
+    for batch in batches() {
+        let roots: Vec<_> = replicas.iter_mut().map(|x| x.execute(&batch)).collect();
+        assert_eq!(roots[0], roots[1]);
+    }
+    assert_eq!(replicas[0].query(&[1u8]), None);
+    assert_eq!(replicas[0].query(&[2u8]), Some(vec![3u8]));
+}
+
+#[test]
+fn order_matters() {
+    let mut replica = KeyValueStore::default();
+    let mut reversed = KeyValueStore::default();
+    let roots: Vec<_> = batches().iter().map(|x| replica.execute(x)).collect();
+    let reversed_roots: Vec<_> = batches()
+        .iter()
+        .rev()
+        .map(|x| reversed.execute(x))
+        .collect();
+    assert_ne!(roots.last(), reversed_roots.last());
+}
+
+#[test]
+fn snapshot_and_restore() {
+    let mut replica = KeyValueStore::default();
+    let batches = batches();
+    replica.execute(&batches[0]);
+
+    // A new replica restores the snapshot and catches up.
+    let mut restored = KeyValueStore::default();
+    restored.restore(&replica.snapshot()).unwrap();
+    assert_eq!(restored.root(), replica.root());
+    assert_eq!(restored.execute(&batches[1]), replica.execute(&batches[1]));
+
+    assert!(restored.restore(&[1u8]).is_err());
+}
@@ -0,0 +1,13 @@
+use super::*;
+
+#[test]
+fn reject_certificates_mismatch() {
+    let mut parameters = Parameters::default();
+    assert!(parameters.validate().is_ok());
+
+    parameters.mempool.availability_certificates = true;
+    assert!(parameters.validate().is_err());
+
+    parameters.consensus.availability_certificates = true;
+    assert!(parameters.validate().is_ok());
+}
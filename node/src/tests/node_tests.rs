@@ -0,0 +1,178 @@
+use super::*;
+use crate::application::{KeyValueCommand, KeyValueStore};
+use consensus::Proof;
+use crypto::{generate_keypair, PublicKey, Signature};
+use mempool::SignedTransaction;
+use rand::rngs::StdRng;
+use rand::SeedableRng as _;
+use std::fs;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+fn batch(epoch: EpochNumber, key: u8, value: u8) -> CommittedBatch {
+    let command = KeyValueCommand::Set(vec![key], vec![value]);
+    CommittedBatch {
+        epoch,
+        block_digest: Digest([epoch as u8; 32]),
+        transactions: vec![bincode::serialize(&command).unwrap()],
+    }
+}
+
+// Execute the committed batches with a fresh key-value application, and return it once the
+// commit channel is closed.
+fn spawn_node(
+    store: Store,
+    execution: ExecutionState,
+    snapshot_interval: EpochNumber,
+) -> (
+    Sender<CommittedBatch>,
+    Sender<Query>,
+    JoinHandle<KeyValueStore>,
+) {
+    let (tx_batch, rx_batch) = channel(10);
+    let (tx_query, rx_query) = channel(10);
+    let mut node = Node {
+        commit: rx_batch,
+        pacing: PacingMetrics::default(),
+        batching: BatchingMetrics::default(),
+        store,
+        queries: rx_query,
+        execution,
+        snapshot_interval,
+    };
+    let handle = tokio::spawn(async move {
+        let mut application = KeyValueStore::default();
+        node.execute(&mut application).await;
+        application
+    });
+    (tx_batch, tx_query, handle)
+}
+
+#[tokio::test]
+async fn query_application() {
+    let path = ".db_test_query_application";
+    let _ = fs::remove_dir_all(path);
+    let mut store = Store::new(path).unwrap();
+    let (tx_batch, tx_query, _handle) = spawn_node(store.clone(), ExecutionState::default(), 1);
+
+    // Wait for the batch to be executed, and query its key.
+    tx_batch.send(batch(1, 1, 1)).await.unwrap();
+    store.notify_read(STATE_KEY.to_vec()).await.unwrap();
+    let (sender, receiver) = oneshot::channel();
+    tx_query.send((vec![1u8], sender)).await.unwrap();
+    assert_eq!(receiver.await.unwrap(), Some(vec![1u8]));
+}
+
+#[tokio::test]
+async fn restore_after_restart() {
+    let path = ".db_test_restore_after_restart";
+    let _ = fs::remove_dir_all(path);
+    let store = Store::new(path).unwrap();
+    let execution = ExecutionState::default();
+    let (tx_batch, _tx_query, handle) = spawn_node(store.clone(), execution.clone(), 10);
+    tx_batch.send(batch(1, 1, 1)).await.unwrap();
+    tx_batch.send(batch(2, 2, 2)).await.unwrap();
+    drop(tx_batch);
+    let application = handle.await.unwrap();
+
+    // The node persisted its state on shutdown, and the mempool may collect the payloads.
+    assert_eq!(execution.progress.executed(), 2);
+
+    // The restarted node restores the state, and skips the batches it already executed.
+    let (tx_batch, _tx_query, handle) = spawn_node(store, ExecutionState::default(), 10);
+    tx_batch.send(batch(2, 2, 3)).await.unwrap();
+    drop(tx_batch);
+    let restored = handle.await.unwrap();
+    assert_eq!(restored.root(), application.root());
+    assert_eq!(restored.query(&[2u8]), Some(vec![2u8]));
+}
+
+#[tokio::test]
+async fn nonces_across_restarts() {
+    let path = ".db_test_nonces_across_restarts";
+    let _ = fs::remove_dir_all(path);
+    let store = Store::new(path).unwrap();
+    let (sender, secret) = generate_keypair(&mut StdRng::from_seed([0; 32]));
+    let signed = |epoch, nonces: Vec<u64>| CommittedBatch {
+        epoch,
+        block_digest: Digest([epoch as u8; 32]),
+        transactions: nonces
+            .into_iter()
+            .map(|x| SignedTransaction::new(sender, x, vec![1u8], &secret).to_transaction())
+            .collect(),
+    };
+
+    // Execute a signed transaction.
+    let nonces = Arc::new(Mutex::new(NonceFilter::default()));
+    let execution = ExecutionState {
+        nonces: Some(nonces.clone()),
+        ..ExecutionState::default()
+    };
+    let (tx_batch, _tx_query, handle) = spawn_node(store.clone(), execution, 1);
+    tx_batch.send(signed(1, vec![1])).await.unwrap();
+    drop(tx_batch);
+    handle.await.unwrap();
+    assert_eq!(nonces.lock().unwrap().nonce(&sender), Some(1));
+
+    // The restarted node restores the nonces with the application state.
+    let nonces = Arc::new(Mutex::new(NonceFilter::default()));
+    let execution = ExecutionState {
+        nonces: Some(nonces.clone()),
+        ..ExecutionState::default()
+    };
+    let (tx_batch, tx_query, handle) = spawn_node(store, execution, 1);
+    let (reply, receiver) = oneshot::channel();
+    tx_query.send((vec![1u8], reply)).await.unwrap();
+    receiver.await.unwrap();
+    assert_eq!(nonces.lock().unwrap().nonce(&sender), Some(1));
+
+    // And still drops transactions reusing the nonce.
+    tx_batch.send(signed(2, vec![1, 3])).await.unwrap();
+    drop(tx_batch);
+    handle.await.unwrap();
+    assert_eq!(nonces.lock().unwrap().nonce(&sender), Some(3));
+}
+
+#[tokio::test]
+async fn refuse_mismatched_state() {
+    let path = ".db_test_refuse_mismatched_state";
+    let _ = fs::remove_dir_all(path);
+    let mut store = Store::new(path).unwrap();
+    let epoch: EpochNumber = 1;
+    let persist = |digest: &Digest| {
+        bincode::serialize(&(epoch, digest, Vec::<u8>::new(), None::<NonceFilter>)).unwrap()
+    };
+
+    // A state persisted without the committed chain, e.g. before the chain was reset.
+    let value = persist(&Digest::default());
+    store.write(STATE_KEY.to_vec(), value).await;
+    assert!(matches!(
+        Node::checkpoint(&mut store).await,
+        Err(NodeError::StateMismatch(1))
+    ));
+
+    // Or for another block than the one committed in its epoch.
+    let block = Block {
+        author: PublicKey::default(),
+        parent: Digest::default(),
+        view: 1,
+        timestamp: 0,
+        payload: Vec::new(),
+        certificates: Vec::new(),
+        signature: Signature::default(),
+        epoch: 1,
+        proof: Proof::Pi(Vec::new()),
+    };
+    let value = bincode::serialize(&block).unwrap();
+    store.write(CommittedBlock::key(1), value).await;
+    assert!(matches!(
+        Node::checkpoint(&mut store).await,
+        Err(NodeError::StateMismatch(1))
+    ));
+
+    // A state persisted after executing that block resumes from its epoch.
+    let value = persist(&block.digest());
+    store.write(STATE_KEY.to_vec(), value).await;
+    assert_eq!(Node::checkpoint(&mut store).await.unwrap(), 1);
+}
@@ -0,0 +1,152 @@
+use super::*;
+use consensus::Proof;
+use crypto::{PublicKey, Signature};
+use mempool::{transaction_digest, Receipt, Reply};
+use std::fs;
+use tokio::sync::mpsc::channel;
+
+// Persist a block committing one payload with the given transactions, as consensus does, and
+// hand it to the pipeline.
+async fn commit(
+    store: &mut Store,
+    tx_commit: &Sender<CommittedBlock>,
+    epoch: EpochNumber,
+    transactions: Vec<Transaction>,
+) {
+    let payload = Payload {
+        transactions,
+        author: PublicKey::default(),
+        signature: Signature::default(),
+    };
+    let digest = payload.digest();
+    store
+        .write(digest.to_vec(), bincode::serialize(&payload).unwrap())
+        .await;
+    let block = Block {
+        author: PublicKey::default(),
+        parent: Digest::default(),
+        view: 1,
+        timestamp: 0,
+        payload: vec![digest],
+        certificates: Vec::new(),
+        signature: Signature::default(),
+        epoch,
+        proof: Proof::Pi(Vec::new()),
+    };
+    let value = bincode::serialize(&block).unwrap();
+    store.write(CommittedBlock::key(epoch), value).await;
+    let committed = CommittedBlock {
+        block,
+        beacon: None,
+        timestamp: 0,
+    };
+    tx_commit.send(committed).await.unwrap();
+}
+
+#[tokio::test]
+async fn filter_across_restarts() {
+    let path = ".db_test_filter_across_restarts";
+    let _ = fs::remove_dir_all(path);
+    let mut store = Store::new(path).unwrap();
+
+    // Commit a transaction.
+    let (tx_commit, rx_commit) = channel(1);
+    let (tx_batch, mut rx_batch) = channel(1);
+    Pipeline::spawn(
+        store.clone(),
+        rx_commit,
+        tx_batch,
+        10,
+        0,
+        ExecutionState::default(),
+    );
+    commit(&mut store, &tx_commit, 1, vec![vec![1u8]]).await;
+    let batch = rx_batch.recv().await.unwrap();
+    assert_eq!(batch.transactions, vec![vec![1u8]]);
+
+    // Restart the pipeline past the executed batch, and ensure it still filters the
+    // transaction out.
+    drop(tx_commit);
+    assert!(rx_batch.recv().await.is_none());
+    let (tx_commit, rx_commit) = channel(1);
+    let (tx_batch, mut rx_batch) = channel(1);
+    Pipeline::spawn(
+        store.clone(),
+        rx_commit,
+        tx_batch,
+        10,
+        1,
+        ExecutionState::default(),
+    );
+    commit(&mut store, &tx_commit, 2, vec![vec![1u8], vec![2u8]]).await;
+    let batch = rx_batch.recv().await.unwrap();
+    assert_eq!(batch.epoch, 2);
+    assert_eq!(batch.transactions, vec![vec![2u8]]);
+}
+
+#[tokio::test]
+async fn replay_after_restart() {
+    let path = ".db_test_replay_after_restart";
+    let _ = fs::remove_dir_all(path);
+    let mut store = Store::new(path).unwrap();
+
+    // Commit two blocks.
+    let (tx_commit, rx_commit) = channel(1);
+    let (tx_batch, mut rx_batch) = channel(2);
+    let execution = ExecutionState::default();
+    Pipeline::spawn(store.clone(), rx_commit, tx_batch, 10, 0, execution);
+    commit(&mut store, &tx_commit, 1, vec![vec![1u8]]).await;
+    commit(&mut store, &tx_commit, 2, vec![vec![2u8]]).await;
+    assert_eq!(rx_batch.recv().await.unwrap().epoch, 1);
+    assert_eq!(rx_batch.recv().await.unwrap().epoch, 2);
+
+    // Restart the pipeline as if only the first batch was executed, and ensure it replays the
+    // second block from the store before the ones consensus commits next.
+    drop(tx_commit);
+    assert!(rx_batch.recv().await.is_none());
+    let (tx_commit, rx_commit) = channel(1);
+    let (tx_batch, mut rx_batch) = channel(2);
+    let execution = ExecutionState::default();
+    Pipeline::spawn(store.clone(), rx_commit, tx_batch, 10, 1, execution);
+    let batch = rx_batch.recv().await.unwrap();
+    assert_eq!(batch.epoch, 2);
+    assert_eq!(batch.transactions, vec![vec![2u8]]);
+    commit(&mut store, &tx_commit, 3, vec![vec![3u8]]).await;
+    assert_eq!(rx_batch.recv().await.unwrap().epoch, 3);
+}
+
+#[tokio::test]
+async fn receipts_once_resolved() {
+    let path = ".db_test_receipts_once_resolved";
+    let _ = fs::remove_dir_all(path);
+    let mut store = Store::new(path).unwrap();
+
+    // A client waits for its transaction to be committed.
+    let (tx_reply, mut rx_reply) = channel(1);
+    let execution = ExecutionState::default();
+    let transaction = transaction_digest(&vec![2u8]);
+    execution
+        .receipts
+        .lock()
+        .unwrap()
+        .subscribe(transaction.clone(), tx_reply);
+
+    // Commit it, and ensure the client learns where it landed.
+    let (tx_commit, rx_commit) = channel(1);
+    let (tx_batch, mut rx_batch) = channel(1);
+    Pipeline::spawn(store.clone(), rx_commit, tx_batch, 10, 0, execution);
+    commit(&mut store, &tx_commit, 1, vec![vec![1u8], vec![2u8]]).await;
+    let batch = rx_batch.recv().await.unwrap();
+    match rx_reply.recv().await {
+        Some(Reply::Committed(receipt)) => assert_eq!(
+            receipt,
+            Receipt {
+                transaction,
+                epoch: 1,
+                block: batch.block_digest,
+                position: 1,
+            }
+        ),
+        x => panic!("Unexpected reply: {:?}", x),
+    }
+}
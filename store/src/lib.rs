@@ -1,3 +1,4 @@
+use rocksdb::{Direction, IteratorMode};
 use std::collections::{HashMap, VecDeque};
 use tokio::sync::mpsc::{channel, Sender};
 use tokio::sync::oneshot;
@@ -16,6 +17,9 @@ pub enum StoreCommand {
     Write(Key, Value),
     Read(Key, oneshot::Sender<StoreResult<Option<Value>>>),
     NotifyRead(Key, oneshot::Sender<StoreResult<Value>>),
+    Delete(Key),
+    Scan(Key, oneshot::Sender<StoreResult<Vec<(Key, Value)>>>),
+    DiskUsage(oneshot::Sender<StoreResult<u64>>),
 }
 
 #[derive(Clone)]
@@ -55,6 +59,25 @@ impl Store {
                             }
                         }
                     }
+                    StoreCommand::Delete(key) => {
+                        let _ = db.delete(&key);
+                    }
+                    StoreCommand::Scan(prefix, sender) => {
+                        let response = db
+                            .iterator(IteratorMode::From(&prefix, Direction::Forward))
+                            .map(|x| x.map(|(k, v)| (k.to_vec(), v.to_vec())))
+                            .take_while(|x| {
+                                x.as_ref().map_or(true, |(k, _)| k.starts_with(&prefix))
+                            })
+                            .collect();
+                        let _ = sender.send(response);
+                    }
+                    StoreCommand::DiskUsage(sender) => {
+                        let response = db
+                            .property_int_value("rocksdb.total-sst-files-size")
+                            .map(|x| x.unwrap_or(0));
+                        let _ = sender.send(response);
+                    }
                 }
             }
         });
@@ -77,6 +100,34 @@ impl Store {
             .expect("Failed to receive reply to Read command from store")
     }
 
+    pub async fn delete(&mut self, key: Key) {
+        if let Err(e) = self.channel.send(StoreCommand::Delete(key)).await {
+            panic!("Failed to send Delete command to store: {}", e);
+        }
+    }
+
+    // Read all the entries whose key starts with `prefix`, in key order.
+    pub async fn scan(&mut self, prefix: Key) -> StoreResult<Vec<(Key, Value)>> {
+        let (sender, receiver) = oneshot::channel();
+        if let Err(e) = self.channel.send(StoreCommand::Scan(prefix, sender)).await {
+            panic!("Failed to send Scan command to store: {}", e);
+        }
+        receiver
+            .await
+            .expect("Failed to receive reply to Scan command from store")
+    }
+
+    // The size of the files of the store on disk, in bytes.
+    pub async fn disk_usage(&mut self) -> StoreResult<u64> {
+        let (sender, receiver) = oneshot::channel();
+        if let Err(e) = self.channel.send(StoreCommand::DiskUsage(sender)).await {
+            panic!("Failed to send DiskUsage command to store: {}", e);
+        }
+        receiver
+            .await
+            .expect("Failed to receive reply to DiskUsage command from store")
+    }
+
     pub async fn notify_read(&mut self, key: Key) -> StoreResult<Value> {
         let (sender, receiver) = oneshot::channel();
         if let Err(e) = self
@@ -71,3 +71,33 @@ async fn read_notify() {
     store.write(key, value).await;
     assert!(handle.await.is_ok());
 }
+
+#[tokio::test]
+async fn delete_and_scan() {
+    // Create new store.
+    let path = ".db_test_delete_and_scan";
+    let _ = fs::remove_dir_all(path);
+    let mut store = Store::new(path).unwrap();
+
+    // Write values under two prefixes.
+    store.write(vec![0u8, 1u8], vec![1u8]).await;
+    store.write(vec![1u8, 1u8], vec![2u8]).await;
+    store.write(vec![1u8, 2u8], vec![3u8]).await;
+    store.write(vec![2u8, 1u8], vec![4u8]).await;
+
+    // Scan one prefix.
+    let result = store.scan(vec![1u8]).await;
+    assert!(result.is_ok());
+    let entries = result.unwrap();
+    assert_eq!(
+        entries,
+        vec![(vec![1u8, 1u8], vec![2u8]), (vec![1u8, 2u8], vec![3u8])]
+    );
+
+    // Delete one of its keys.
+    store.delete(vec![1u8, 1u8]).await;
+    let result = store.read(vec![1u8, 1u8]).await;
+    assert!(result.unwrap().is_none());
+    let entries = store.scan(vec![1u8]).await.unwrap();
+    assert_eq!(entries, vec![(vec![1u8, 2u8], vec![3u8])]);
+}